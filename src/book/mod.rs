@@ -0,0 +1,264 @@
+pub mod keys;
+
+use crate::tools::rng::Rng;
+use cozy_chess::{Board, Move, Piece, Square};
+use keys::polyglot_key;
+use std::{fmt, fs, io};
+
+pub const DEFAULT_BOOK_DEPTH: u16 = 20;
+
+// A 16 byte, big endian Polyglot book entry.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+const PROMOTIONS: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+// Polyglot writes castling as the king taking its own rook, just like cozy-chess,
+// so only the promotion piece needs translating.
+pub fn decode_move(mv: u16) -> Option<Move> {
+    let promotion = match (mv >> 12) & 0x7 {
+        0 => None,
+        p => Some(*PROMOTIONS.get(p as usize - 1)?),
+    };
+
+    Some(Move {
+        from: Square::index(((mv >> 6) & 0x3F) as usize),
+        to: Square::index((mv & 0x3F) as usize),
+        promotion,
+    })
+}
+
+pub fn encode_move(mv: Move) -> u16 {
+    let promotion = mv
+        .promotion
+        .and_then(|p| PROMOTIONS.iter().position(|&q| q == p))
+        .map_or(0, |p| p as u16 + 1);
+
+    promotion << 12 | (mv.from as u16) << 6 | mv.to as u16
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    WrongLength,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "{e}"),
+            BookError::WrongLength => write!(f, "not a multiple of {} bytes", BookEntry::SIZE),
+        }
+    }
+}
+
+pub struct Book {
+    // Sorted by key.
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(BookEntry::SIZE) {
+            return Err(BookError::WrongLength);
+        }
+
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(BookEntry::SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Book { entries })
+    }
+
+    pub fn load(path: &str) -> Result<Self, BookError> {
+        Book::from_bytes(&fs::read(path).map_err(BookError::Io)?)
+    }
+
+    pub fn num_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Legal book moves and their weights. Entries that do not decode to a legal
+    // move are ignored, as they are most likely key collisions.
+    pub fn moves(&self, board: &Board) -> Vec<(Move, u16)> {
+        let key = polyglot_key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| Some((decode_move(entry.mv)?, entry.weight)))
+            .filter(|&(mv, _)| board.is_legal(mv))
+            .collect()
+    }
+
+    // Picks the most played move, or a random move weighted by how often it was played.
+    pub fn probe(&self, board: &Board, best: bool, rng: &mut Rng) -> Option<Move> {
+        let moves = self.moves(board);
+
+        if best {
+            return moves
+                .iter()
+                .max_by_key(|&&(_, weight)| weight)
+                .map(|&(mv, _)| mv);
+        }
+
+        let total: usize = moves.iter().map(|&(_, weight)| weight as usize).sum();
+        if total == 0 {
+            return moves.first().map(|&(mv, _)| mv);
+        }
+
+        let mut pick = rng.below(total);
+        moves.into_iter().find_map(|(mv, weight)| {
+            if pick < weight as usize {
+                return Some(mv);
+            }
+            pick -= weight as usize;
+            None
+        })
+    }
+}
+
+// The UCI book options.
+pub struct BookOptions {
+    pub book: Option<Book>,
+    pub own_book: bool,
+    // In full moves.
+    pub depth: u16,
+    pub best_move: bool,
+    pub rng: Rng,
+}
+
+impl BookOptions {
+    pub fn new() -> Self {
+        BookOptions {
+            book: None,
+            own_book: false,
+            depth: DEFAULT_BOOK_DEPTH,
+            best_move: false,
+            rng: Rng::from_time(),
+        }
+    }
+
+    pub fn probe(&mut self, board: &Board) -> Option<Move> {
+        if !self.own_book || board.fullmove_number() > self.depth {
+            return None;
+        }
+
+        self.book
+            .as_ref()?
+            .probe(board, self.best_move, &mut self.rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(board: &Board, mv: &str, weight: u16) -> BookEntry {
+        BookEntry {
+            key: polyglot_key(board),
+            mv: encode_move(mv.parse().unwrap()),
+            weight,
+            learn: 0,
+        }
+    }
+
+    fn test_book() -> (Book, Board) {
+        let startpos = Board::startpos();
+        let castling: Board =
+            "r3k2r/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R3K2R w KQkq - 4 8"
+                .parse()
+                .unwrap();
+
+        // Deliberately unsorted, with an illegal move.
+        let entries = [
+            entry(&castling, "e1h1", 5),
+            entry(&startpos, "e2e4", 3),
+            entry(&startpos, "e2e5", 100),
+            entry(&castling, "e1a1", 1),
+            entry(&startpos, "d2d4", 1),
+        ];
+        let bytes: Vec<u8> = entries.iter().flat_map(|e| e.to_bytes()).collect();
+
+        (Book::from_bytes(&bytes).unwrap(), castling)
+    }
+
+    #[test]
+    fn moves() {
+        let (book, castling) = test_book();
+        let startpos = Board::startpos();
+
+        let mut moves = book.moves(&startpos);
+        moves.sort_by_key(|&(_, weight)| weight);
+        assert_eq!(
+            moves,
+            [("d2d4".parse().unwrap(), 1), ("e2e4".parse().unwrap(), 3)]
+        );
+
+        // Castling comes out in the engine's king-takes-rook notation.
+        let mut rng = Rng::new(0);
+        assert_eq!(
+            book.probe(&castling, true, &mut rng),
+            Some("e1h1".parse().unwrap())
+        );
+
+        let after_e4 = {
+            let mut board = startpos.clone();
+            board.play("e2e4".parse().unwrap());
+            board
+        };
+        assert!(book.moves(&after_e4).is_empty());
+    }
+
+    #[test]
+    fn weighted() {
+        let (book, _) = test_book();
+        let mut rng = Rng::new(1);
+        let e4: Move = "e2e4".parse().unwrap();
+
+        let hits = (0..4000)
+            .filter(|_| book.probe(&Board::startpos(), false, &mut rng) == Some(e4))
+            .count();
+        assert!((2700..3300).contains(&hits), "{hits}");
+    }
+
+    #[test]
+    fn encoding() {
+        for mv in ["e2e4", "e1h1", "a7a8q", "h2g1n", "b7c8r", "d2d1b"] {
+            let mv: Move = mv.parse().unwrap();
+            assert_eq!(decode_move(encode_move(mv)), Some(mv));
+        }
+
+        assert!(Book::from_bytes(&[0; 17]).is_err());
+    }
+}
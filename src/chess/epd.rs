@@ -0,0 +1,198 @@
+use super::parse_move::{parse_move, parse_san};
+use cozy_chess::{Board, Move};
+use std::fmt;
+
+// A position with its EPD operations, e.g. `bm Nf3; id "test 1";`.
+#[derive(Clone, Debug)]
+pub struct Epd {
+    pub board: Board,
+    pub ops: Vec<(String, Vec<String>)>,
+}
+
+// Splits `bm Nf3 Nc3; id "a; b";` into opcodes and operands, keeping quoted strings whole.
+fn parse_ops(s: &str) -> Vec<(String, Vec<String>)> {
+    let mut ops = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                if quoted {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                quoted = !quoted;
+            }
+            c if quoted => token.push(c),
+            ';' | ' ' | '\t' => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                if c == ';' && !tokens.is_empty() {
+                    let opcode = tokens.remove(0);
+                    ops.push((opcode, std::mem::take(&mut tokens)));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    // A last operation without its `;`
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    if !tokens.is_empty() {
+        let opcode = tokens.remove(0);
+        ops.push((opcode, tokens));
+    }
+
+    ops
+}
+
+impl Epd {
+    // Accepts EPD lines and full FENs, optionally followed by operations.
+    pub fn parse(line: &str) -> Option<Epd> {
+        let line = line.trim();
+        let mut fields = Vec::new();
+        let mut rest = line;
+        for _ in 0..4 {
+            let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return None;
+            }
+            fields.push(field);
+            rest = tail.trim_start();
+        }
+
+        // The clocks of a full FEN
+        let mut clocks = rest.split_ascii_whitespace();
+        let (halfmove, fullmove) = match (clocks.next(), clocks.next()) {
+            (Some(h), Some(f)) if h.parse::<u8>().is_ok() && f.parse::<u16>().is_ok() => {
+                rest = rest.trim_start()[h.len()..].trim_start()[f.len()..].trim_start();
+                (h.to_string(), f.to_string())
+            }
+            _ => ("0".to_string(), "1".to_string()),
+        };
+
+        let ops = parse_ops(rest);
+        let op = |name: &str| {
+            ops.iter()
+                .find(|(opcode, operands)| opcode == name && operands.len() == 1)
+                .map(|(_, operands)| operands[0].clone())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            op("hmvc").unwrap_or(halfmove),
+            op("fmvn").unwrap_or(fullmove)
+        );
+        let board = Board::from_fen(&fen, false)
+            .or_else(|_| Board::from_fen(&fen, true))
+            .ok()?;
+
+        Some(Epd { board, ops })
+    }
+
+    pub fn op(&self, opcode: &str) -> Option<&[String]> {
+        self.ops
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    // Replaces the operation if it already exists.
+    pub fn set(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.ops.iter_mut().find(|(op, _)| op == opcode) {
+            Some((_, old)) => *old = operands,
+            None => self.ops.push((opcode.to_string(), operands)),
+        }
+    }
+
+    // Moves in an operation such as `bm`, in SAN or coordinate notation.
+    pub fn moves(&self, opcode: &str) -> Vec<Move> {
+        self.op(opcode)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|mv| {
+                parse_san(&self.board, mv)
+                    .ok()
+                    .or_else(|| parse_move(&self.board, mv).ok())
+                    .filter(|&mv| self.board.is_legal(mv))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The first four FEN fields
+        let fen = self.board.to_string();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+        write!(f, "{}", fields.join(" "))?;
+
+        for (opcode, operands) in &self.ops {
+            write!(f, " {opcode}")?;
+            for operand in operands {
+                if operand.contains([' ', ';', '"']) || operand.is_empty() {
+                    write!(f, " \"{}\"", operand.replace('"', ""))?;
+                } else if opcode == "id" || matches!(opcode.as_bytes(), [b'c', b'0'..=b'9']) {
+                    write!(f, " \"{operand}\"")?;
+                } else {
+                    write!(f, " {operand}")?;
+                }
+            }
+            write!(f, ";")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let epd = Epd::parse(
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6 Qh4; id "WAC.001"; c0 "a; b";"#,
+        )
+        .unwrap();
+
+        assert_eq!(epd.op("id"), Some(["WAC.001".to_string()].as_slice()));
+        assert_eq!(epd.op("c0"), Some(["a; b".to_string()].as_slice()));
+        assert_eq!(
+            epd.moves("bm"),
+            ["g3g6".parse().unwrap(), "g3h4".parse().unwrap()]
+        );
+        assert!(epd.moves("am").is_empty());
+        assert_eq!(
+            epd.to_string(),
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6 Qh4; id "WAC.001"; c0 "a; b";"#
+        );
+    }
+
+    #[test]
+    fn fen_and_clocks() {
+        let fen = "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - 3 54";
+        assert_eq!(Epd::parse(fen).unwrap().board, fen.parse().unwrap());
+
+        let epd =
+            Epd::parse("8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - hmvc 3; fmvn 54;").unwrap();
+        assert_eq!(epd.board, fen.parse().unwrap());
+
+        let epd = Epd::parse(&format!("{fen} bm Kg3;")).unwrap();
+        assert_eq!(epd.moves("bm"), ["f3g3".parse().unwrap()]);
+
+        assert!(Epd::parse("8/8/8 w - -").is_none());
+
+        let mut epd = Epd::parse(&format!("{fen} bm Kg3;")).unwrap();
+        epd.set("bm", vec!["Ke2".to_string()]);
+        epd.set("ce", vec!["-15".to_string()]);
+        assert_eq!(
+            epd.to_string(),
+            "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - bm Ke2; ce -15;"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use cozy_chess::{Board, BoardBuilder, CastleRights, Square};
+
+// Mirrors the board vertically and swaps the colors of every piece, so that the
+// resulting position is the same one seen from the other side.
+pub fn flip_colors(board: &Board) -> Board {
+    let original = BoardBuilder::from_board(board);
+    let mut builder = original.clone();
+
+    for (square, piece) in original.board.iter().enumerate() {
+        let flipped = Square::index(square).flip_rank();
+        builder.board[flipped as usize] = piece.map(|(piece, color)| (piece, !color));
+    }
+
+    builder.side_to_move = !original.side_to_move;
+    builder.castle_rights = [original.castle_rights[1], original.castle_rights[0]];
+    builder.en_passant = original.en_passant.map(|square| square.flip_rank());
+
+    builder.build().unwrap()
+}
+
+// Mirrors the board horizontally. Castling is not symmetric along the files, so
+// positions with castling rights cannot be mirrored.
+pub fn flip_files(board: &Board) -> Option<Board> {
+    let original = BoardBuilder::from_board(board);
+    let mut builder = original.clone();
+
+    if original.castle_rights != [CastleRights::EMPTY; 2] {
+        return None;
+    }
+
+    for (square, piece) in original.board.iter().enumerate() {
+        let flipped = Square::index(square).flip_file();
+        builder.board[flipped as usize] = *piece;
+    }
+
+    builder.en_passant = original.en_passant.map(|square| square.flip_file());
+
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip() {
+        let tests: [(&str, &str); 3] = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 1",
+                "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w Qk e6 0 1",
+            ),
+            (
+                "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - 3 54",
+                "8/8/1p2pk2/1P1p1p1p/3P3P/1P2K1P1/8/8 b - - 3 54",
+            ),
+        ];
+
+        for (before, after) in tests {
+            let board: Board = before.parse().unwrap();
+            let flipped = flip_colors(&board);
+
+            assert_eq!(flipped, after.parse().unwrap());
+            assert_eq!(flip_colors(&flipped), board);
+        }
+    }
+
+    #[test]
+    fn mirror() {
+        let board: Board = "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - 3 54"
+            .parse()
+            .unwrap();
+        let mirrored = flip_files(&board).unwrap();
+
+        assert_eq!(
+            mirrored,
+            "8/8/1p1k2p1/p3p3/P1P1P1p1/2KP2P1/8/8 w - - 3 54"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(flip_files(&mirrored), Some(board));
+        assert_eq!(flip_files(&Board::startpos()), None);
+    }
+}
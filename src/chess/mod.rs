@@ -1,3 +1,7 @@
+pub mod epd;
+pub mod mirror;
 pub mod move_gen;
+pub mod packed;
 pub mod parse_move;
 pub mod perft;
+pub mod pgn;
@@ -1,4 +1,6 @@
-use cozy_chess::{Board, Color, Move, Piece, Rank, Square};
+use cozy_chess::{Board, Move};
+#[cfg(test)]
+use cozy_chess::{Color, Piece, Rank, Square};
 
 pub fn all_moves(board: &Board) -> Vec<Move> {
     let mut move_list: Vec<Move> = Vec::new();
@@ -11,6 +13,8 @@ pub fn all_moves(board: &Board) -> Vec<Move> {
     move_list
 }
 
+// The capture generator is only exercised by the tests.
+#[cfg(test)]
 pub fn capture_moves(board: &Board) -> Vec<Move> {
     let enemy_pieces = board.colors(!board.side_to_move());
     let mut captures_list: Vec<Move> = Vec::new();
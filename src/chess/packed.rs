@@ -0,0 +1,220 @@
+use cozy_chess::{BitBoard, Board, BoardBuilder, Color, Piece, Rank, Square};
+use std::io::{self, Read, Write};
+
+// Piece code for a rook that can still castle.
+const UNMOVED_ROOK: u8 = 6;
+const NO_EP: u8 = 64;
+
+// A position with its search score and game result in 32 bytes:
+//
+// | bytes | field                                                      |
+// |-------|------------------------------------------------------------|
+// | 0-7   | occupancy, little endian                                   |
+// | 8-23  | one nibble per occupied square: piece | color << 3         |
+// | 24    | en passant square (64 if none) | side to move << 7         |
+// | 25    | halfmove clock                                             |
+// | 26-27 | fullmove number                                            |
+// | 28-29 | score from white's point of view                           |
+// | 30    | result: 0 black win, 1 draw, 2 white win                   |
+// | 31    | unused                                                     |
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PackedBoard {
+    occupancy: u64,
+    pieces: [u8; 16],
+    stm_ep: u8,
+    halfmove: u8,
+    fullmove: u16,
+    pub score: i16,
+    pub wdl: u8,
+    extra: u8,
+}
+
+impl PackedBoard {
+    pub const SIZE: usize = 32;
+
+    pub fn pack(board: &Board, score: i16, wdl: u8) -> Self {
+        let occupied = board.occupied();
+        let mut pieces = [0u8; 16];
+
+        for (i, square) in occupied.into_iter().enumerate() {
+            let color = board.color_on(square).unwrap();
+            let piece = board.piece_on(square).unwrap();
+            let rights = board.castle_rights(color);
+            let castling_rook = piece == Piece::Rook
+                && square.rank() == Rank::First.relative_to(color)
+                && [rights.short, rights.long].contains(&Some(square.file()));
+
+            let code = if castling_rook {
+                UNMOVED_ROOK
+            } else {
+                piece as u8
+            };
+            pieces[i / 2] |= (code | (color as u8) << 3) << (4 * (i % 2));
+        }
+
+        let ep = board.en_passant().map_or(NO_EP, |file| {
+            Square::new(file, Rank::Sixth.relative_to(board.side_to_move())) as u8
+        });
+
+        PackedBoard {
+            occupancy: occupied.0,
+            pieces,
+            stm_ep: ep | (board.side_to_move() as u8) << 7,
+            halfmove: board.halfmove_clock(),
+            fullmove: board.fullmove_number(),
+            score,
+            wdl,
+            extra: 0,
+        }
+    }
+
+    pub fn unpack(&self) -> Option<Board> {
+        let mut builder = BoardBuilder::empty();
+        let mut castling_rooks = Vec::new();
+
+        for (i, square) in BitBoard(self.occupancy).into_iter().enumerate() {
+            let nibble = (self.pieces[i / 2] >> (4 * (i % 2))) & 0xF;
+            let color = Color::index((nibble >> 3) as usize);
+            let piece = match nibble & 0x7 {
+                UNMOVED_ROOK => {
+                    castling_rooks.push((square, color));
+                    Piece::Rook
+                }
+                code => Piece::try_index(code as usize)?,
+            };
+            *builder.square_mut(square) = Some((piece, color));
+        }
+
+        // Which side a rook castles to depends on where its king is.
+        for (square, color) in castling_rooks {
+            let king = Square::ALL
+                .into_iter()
+                .find(|&sq| builder.square(sq) == Some((Piece::King, color)))?;
+            let rights = builder.castle_rights_mut(color);
+            if square.file() > king.file() {
+                rights.short = Some(square.file());
+            } else {
+                rights.long = Some(square.file());
+            }
+        }
+
+        builder.side_to_move = Color::index((self.stm_ep >> 7) as usize);
+        builder.en_passant = match self.stm_ep & 0x7F {
+            NO_EP => None,
+            sq => Some(Square::try_index(sq as usize)?),
+        };
+        builder.halfmove_clock = self.halfmove;
+        builder.fullmove_number = self.fullmove;
+
+        builder.build().ok()
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+
+        bytes[0..8].copy_from_slice(&self.occupancy.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces);
+        bytes[24] = self.stm_ep;
+        bytes[25] = self.halfmove;
+        bytes[26..28].copy_from_slice(&self.fullmove.to_le_bytes());
+        bytes[28..30].copy_from_slice(&self.score.to_le_bytes());
+        bytes[30] = self.wdl;
+        bytes[31] = self.extra;
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        PackedBoard {
+            occupancy: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pieces: bytes[8..24].try_into().unwrap(),
+            stm_ep: bytes[24],
+            halfmove: bytes[25],
+            fullmove: u16::from_le_bytes([bytes[26], bytes[27]]),
+            score: i16::from_le_bytes([bytes[28], bytes[29]]),
+            wdl: bytes[30],
+            extra: bytes[31],
+        }
+    }
+
+    pub fn write<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+// Reads packed boards until the end of the stream.
+pub struct PackedReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> PackedReader<R> {
+    pub fn new(reader: R) -> Self {
+        PackedReader { reader }
+    }
+}
+
+impl<R: Read> Iterator for PackedReader<R> {
+    type Item = io::Result<PackedBoard>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0u8; PackedBoard::SIZE];
+
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Some(Ok(PackedBoard::from_bytes(&bytes))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uci::bench::FENS;
+
+    #[test]
+    fn round_trip() {
+        let fens = FENS.iter().copied().chain([
+            // En passant
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            // Partial castling rights
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 5 40",
+        ]);
+
+        for (i, fen) in fens.enumerate() {
+            let board = Board::from_fen(fen, false).unwrap();
+            let packed = PackedBoard::pack(&board, i as i16 - 20, (i % 3) as u8);
+            let read = PackedBoard::from_bytes(&packed.to_bytes());
+
+            assert_eq!(read, packed);
+            assert_eq!(read.unpack().unwrap(), board, "{fen}");
+            assert_eq!(read.score, i as i16 - 20);
+        }
+    }
+
+    #[test]
+    fn chess960() {
+        // Rooks on the b and g files, king on c.
+        let fen = "1rk3r1/pppppppp/8/8/8/8/PPPPPPPP/1RK3R1 w GBgb - 0 1";
+        let board = Board::from_fen(fen, true).unwrap();
+
+        assert_eq!(PackedBoard::pack(&board, 0, 1).unpack().unwrap(), board);
+    }
+
+    #[test]
+    fn reader() {
+        let boards: Vec<_> = FENS
+            .iter()
+            .map(|fen| PackedBoard::pack(&fen.parse().unwrap(), 0, 1))
+            .collect();
+        let mut bytes = Vec::new();
+        for board in &boards {
+            board.write(&mut bytes).unwrap();
+        }
+
+        let read: Vec<_> = PackedReader::new(bytes.as_slice())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read, boards);
+    }
+}
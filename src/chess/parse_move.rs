@@ -1,4 +1,4 @@
-use cozy_chess::{Board, Move, MoveParseError, Piece, Square};
+use cozy_chess::{Board, File, GameStatus, Move, MoveParseError, Piece, Rank, Square};
 
 pub fn parse_move(board: &Board, movestr: &str) -> Result<Move, MoveParseError> {
     let mut mv: Move = movestr.parse()?;
@@ -30,9 +30,200 @@ pub fn move_to_string(board: &Board, mv: Move) -> String {
     mv.to_string()
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum SanError {
+    Invalid,
+    Illegal,
+    Ambiguous,
+}
+
+fn san_piece(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+fn san_char(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+// Parses Standard Algebraic Notation, e.g. `Nbd7`, `exd6`, `e8=Q+` or `O-O`. Also
+// accepts `0-0`, promotions without the `=` and trailing annotations like `!?`.
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, SanError> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+    if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let rights = board.castle_rights(board.side_to_move());
+        let rook = if san.len() == 3 {
+            rights.short
+        } else {
+            rights.long
+        };
+        let king = board.king(board.side_to_move());
+        let mv = Move {
+            from: king,
+            to: Square::new(rook.ok_or(SanError::Illegal)?, king.rank()),
+            promotion: None,
+        };
+        return board.is_legal(mv).then_some(mv).ok_or(SanError::Illegal);
+    }
+
+    let (san, promotion) = match san.split_once('=') {
+        Some((san, promotion)) => {
+            let promotion = promotion
+                .chars()
+                .next()
+                .and_then(|c| san_piece(c.to_ascii_uppercase()));
+            (san, Some(promotion.ok_or(SanError::Invalid)?))
+        }
+        // `e8Q`
+        None => match san.char_indices().last() {
+            Some((i, c)) if i >= 2 && san[..i].ends_with(['1', '8']) && san_piece(c).is_some() => {
+                (&san[..i], san_piece(c))
+            }
+            _ => (san, None),
+        },
+    };
+
+    let mut chars: Vec<char> = san.chars().filter(|&c| c != 'x').collect();
+    let piece = match chars.first().and_then(|&c| san_piece(c)) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        }
+        None => Piece::Pawn,
+    };
+
+    if chars.len() < 2 {
+        return Err(SanError::Invalid);
+    }
+    let to = chars.split_off(chars.len() - 2);
+    let to = Square::new(
+        File::try_from(to[0]).map_err(|_| SanError::Invalid)?,
+        Rank::try_from(to[1]).map_err(|_| SanError::Invalid)?,
+    );
+
+    // Whatever is left disambiguates the origin square.
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        if let Ok(file) = File::try_from(c) {
+            from_file = Some(file);
+        } else if let Ok(rank) = Rank::try_from(c) {
+            from_rank = Some(rank);
+        } else {
+            return Err(SanError::Invalid);
+        }
+    }
+
+    let mut found = None;
+    let mut ambiguous = false;
+    board.generate_moves_for(board.colored_pieces(board.side_to_move(), piece), |moves| {
+        for mv in moves {
+            if mv.to == to
+                && mv.promotion == promotion
+                && from_file.is_none_or(|file| mv.from.file() == file)
+                && from_rank.is_none_or(|rank| mv.from.rank() == rank)
+                // The king taking its own rook is castling, not a king move.
+                && board.color_on(mv.to) != Some(board.side_to_move())
+            {
+                ambiguous |= found.is_some();
+                found = Some(mv);
+            }
+        }
+        false
+    });
+
+    match found {
+        _ if ambiguous => Err(SanError::Ambiguous),
+        Some(mv) => Ok(mv),
+        None => Err(SanError::Illegal),
+    }
+}
+
+// Formats a move in Standard Algebraic Notation, with only as much disambiguation
+// as is needed and a `+` or `#` suffix.
+pub fn move_to_san(board: &Board, mv: Move) -> String {
+    let stm = board.side_to_move();
+    let piece = board.piece_on(mv.from).unwrap();
+    let mut san = String::new();
+
+    if piece == Piece::King && board.color_on(mv.to) == Some(stm) {
+        san.push_str(if mv.to.file() > mv.from.file() {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let capture = board.color_on(mv.to).is_some()
+            || (piece == Piece::Pawn && mv.from.file() != mv.to.file());
+
+        if piece == Piece::Pawn {
+            if capture {
+                san.push(mv.from.file().into());
+            }
+        } else {
+            san.push(san_char(piece));
+
+            let mut others = Vec::new();
+            board.generate_moves_for(board.colored_pieces(stm, piece), |moves| {
+                others.extend(
+                    moves
+                        .into_iter()
+                        .filter(|m| m.to == mv.to && m.from != mv.from),
+                );
+                false
+            });
+
+            if !others.is_empty() {
+                if others.iter().all(|m| m.from.file() != mv.from.file()) {
+                    san.push(mv.from.file().into());
+                } else if others.iter().all(|m| m.from.rank() != mv.from.rank()) {
+                    san.push(mv.from.rank().into());
+                } else {
+                    san.push_str(&mv.from.to_string());
+                }
+            }
+        }
+
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(san_char(promotion));
+        }
+    }
+
+    let mut after = board.clone();
+    after.play_unchecked(mv);
+    if !after.checkers().is_empty() {
+        san.push(if after.status() == GameStatus::Won {
+            '#'
+        } else {
+            '+'
+        });
+    }
+
+    san
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_move;
+    use super::{move_to_san, parse_move, parse_san, SanError};
     use crate::chess::parse_move::move_to_string;
     use cozy_chess::{Board, Move};
 
@@ -116,4 +307,104 @@ mod tests {
             assert_eq!(move_to_string(&board, before), after);
         }
     }
+
+    #[test]
+    fn san() {
+        let tests: [(&str, &str, &str); 7] = [
+            ("startpos", "e4", "e2e4"),
+            ("startpos", "Nf3", "g1f3"),
+            (
+                "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4",
+                "Nxe5",
+                "f3e5",
+            ),
+            (
+                "r3k2r/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R3K2R w KQkq - 4 8",
+                "O-O-O",
+                "e1a1",
+            ),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q+", "b7b8q"),
+            ("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "Rhf1", "h1f1"),
+            ("4k3/8/8/8/R7/8/8/R3K3 w Q - 0 1", "R4a2", "a4a2"),
+        ];
+
+        for (fen, san, uci) in tests {
+            let board = match fen {
+                "startpos" => Board::startpos(),
+                fen => fen.parse().unwrap(),
+            };
+            assert_eq!(parse_san(&board, san), Ok(uci.parse().unwrap()), "{san}");
+        }
+
+        let board: Board = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1".parse().unwrap();
+        assert_eq!(parse_san(&board, "Rd1"), Err(SanError::Ambiguous));
+        assert_eq!(parse_san(&board, "Rd2"), Err(SanError::Illegal));
+        assert_eq!(parse_san(&board, "Zd2"), Err(SanError::Invalid));
+    }
+
+    #[test]
+    fn san_variants() {
+        let tests: [(&str, &str, &str); 6] = [
+            ("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "0-0", "e1h1"),
+            ("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "0-0-0+", "e1a1"),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8Q", "b7b8q"),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=n", "b7b8n"),
+            ("startpos", "e4!?", "e2e4"),
+            ("startpos", "Nf3??", "g1f3"),
+        ];
+
+        for (fen, san, uci) in tests {
+            let board = match fen {
+                "startpos" => Board::startpos(),
+                fen => fen.parse().unwrap(),
+            };
+            assert_eq!(parse_san(&board, san), Ok(uci.parse().unwrap()), "{san}");
+        }
+
+        // Chess960, with the king on e1 and rooks on b1 and g1.
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1", true).unwrap();
+        assert_eq!(parse_san(&board, "O-O"), Ok("e1g1".parse().unwrap()));
+        assert_eq!(parse_san(&board, "O-O-O"), Ok("e1b1".parse().unwrap()));
+    }
+
+    #[test]
+    fn to_san() {
+        let tests: [(&str, &str, &str); 9] = [
+            ("startpos", "g1f3", "Nf3"),
+            (
+                "r3k2r/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R3K2R w KQkq - 4 8",
+                "e1h1",
+                "O-O",
+            ),
+            ("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1", "e1b1", "O-O-O"),
+            ("4k3/8/8/8/R7/8/8/R3K3 w Q - 0 1", "a4a2", "R4a2"),
+            ("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "h1d1", "Rhd1"),
+            ("7k/8/4Q3/8/2Q1Q3/8/8/K7 w - - 0 1", "e4c6", "Qe4c6"),
+            ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q", "b8=Q+"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "Ra8#"),
+        ];
+
+        for (fen, uci, san) in tests {
+            let board = match fen {
+                "startpos" => Board::startpos(),
+                fen => Board::from_fen(fen, fen.contains("GB")).unwrap(),
+            };
+            assert_eq!(move_to_san(&board, uci.parse().unwrap()), san);
+        }
+    }
+
+    #[test]
+    fn san_round_trip() {
+        for fen in crate::uci::bench::FENS {
+            let board: Board = fen.parse().unwrap();
+            board.generate_moves(|moves| {
+                for mv in moves {
+                    let san = move_to_san(&board, mv);
+                    assert_eq!(parse_san(&board, &san), Ok(mv), "{fen} {san}");
+                }
+                false
+            });
+        }
+    }
 }
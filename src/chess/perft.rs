@@ -1,3 +1,14 @@
+use cozy_chess::{Board, File, GameStatus, Move, Piece, Square};
+use std::{
+    fmt,
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    thread,
+};
+
 pub fn perft(board: &cozy_chess::Board, depth: u8) -> u64 {
     if depth == 0 {
         1
@@ -22,9 +33,277 @@ pub fn perft(board: &cozy_chess::Board, depth: u8) -> u64 {
     }
 }
 
+// The breakdown of the leaf nodes, as in the chessprogramming.org perft tables.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passant: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    // Checks given only by pieces other than the one that moved.
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+impl AddAssign for PerftStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passant += other.en_passant;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks += other.double_checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+impl fmt::Display for PerftStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes {} captures {} ep {} castles {} promotions {} checks {} discovered {} double {} checkmates {}",
+            self.nodes,
+            self.captures,
+            self.en_passant,
+            self.castles,
+            self.promotions,
+            self.checks,
+            self.discovered_checks,
+            self.double_checks,
+            self.checkmates
+        )
+    }
+}
+
+fn leaf_stats(board: &Board, mv: Move) -> PerftStats {
+    let stm = board.side_to_move();
+    let piece = board.piece_on(mv.from).unwrap();
+    let castle = piece == Piece::King && board.color_on(mv.to) == Some(stm);
+    let en_passant =
+        piece == Piece::Pawn && mv.from.file() != mv.to.file() && board.piece_on(mv.to).is_none();
+
+    let mut after = board.clone();
+    after.play_unchecked(mv);
+    let checkers = after.checkers();
+
+    // Where the piece that moved ends up. Castling moves the king onto the g or
+    // c file, and the rook, which is the one that can give check, onto f or d.
+    let moved_to = if castle {
+        let file = if mv.to.file() > mv.from.file() {
+            File::F
+        } else {
+            File::D
+        };
+        Square::new(file, mv.from.rank())
+    } else {
+        mv.to
+    };
+
+    PerftStats {
+        nodes: 1,
+        captures: (board.color_on(mv.to) == Some(!stm) || en_passant) as u64,
+        en_passant: en_passant as u64,
+        castles: castle as u64,
+        promotions: mv.promotion.is_some() as u64,
+        checks: !checkers.is_empty() as u64,
+        discovered_checks: (!checkers.is_empty() && !checkers.has(moved_to)) as u64,
+        double_checks: (checkers.len() > 1) as u64,
+        checkmates: (after.status() == GameStatus::Won) as u64,
+    }
+}
+
+pub fn perft_detailed(board: &Board, depth: u8) -> PerftStats {
+    let mut stats = PerftStats::default();
+    if depth == 0 {
+        stats.nodes = 1;
+        return stats;
+    }
+
+    board.generate_moves(|moves| {
+        for mv in moves {
+            if depth == 1 {
+                stats += leaf_stats(board, mv);
+            } else {
+                let mut board = board.clone();
+                board.play_unchecked(mv);
+                stats += perft_detailed(&board, depth - 1);
+            }
+        }
+        false
+    });
+
+    stats
+}
+
+// Node counts by Zobrist hash and depth, shared between threads without locks. Each
+// entry stores the data and the key xor the data, so a torn write never matches.
+pub struct PerftTable {
+    entries: Vec<[AtomicU64; 2]>,
+}
+
+impl PerftTable {
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / std::mem::size_of::<[AtomicU64; 2]>()).max(1);
+
+        PerftTable {
+            entries: (0..len)
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        }
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.entries.len() as u64) as usize
+    }
+
+    // The depth is mixed into the key, and kept in the top byte of the data.
+    fn key(hash: u64, depth: u8) -> u64 {
+        hash ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn probe(&self, hash: u64, depth: u8) -> Option<u64> {
+        let key = Self::key(hash, depth);
+        let [check, data] = &self.entries[self.index(key)];
+        let (check, data) = (check.load(Ordering::Relaxed), data.load(Ordering::Relaxed));
+
+        (check ^ data == key && data >> 56 == depth as u64).then_some(data & (u64::MAX >> 8))
+    }
+
+    fn store(&self, hash: u64, depth: u8, nodes: u64) {
+        let key = Self::key(hash, depth);
+        let data = (depth as u64) << 56 | nodes;
+        let [check, stored] = &self.entries[self.index(key)];
+
+        check.store(key ^ data, Ordering::Relaxed);
+        stored.store(data, Ordering::Relaxed);
+    }
+}
+
+fn perft_hashed(board: &Board, depth: u8, table: &PerftTable) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    if let Some(nodes) = table.probe(board.hash(), depth) {
+        return nodes;
+    }
+
+    let mut nodes = 0;
+    board.generate_moves(|moves| {
+        for mv in moves {
+            let mut board = board.clone();
+            board.play_unchecked(mv);
+            nodes += perft_hashed(&board, depth - 1, table);
+        }
+        false
+    });
+
+    table.store(board.hash(), depth, nodes);
+    nodes
+}
+
+// How `perft` and `split` run, set by the Threads and Hash options.
+#[derive(Clone)]
+pub struct PerftOptions {
+    pub threads: usize,
+    pub hash_mb: usize,
+    // Allocated on first use, as GUIs set Hash for engines that never run perft.
+    // Shared between clones, so the copies made for each command reuse it.
+    table: Arc<OnceLock<PerftTable>>,
+}
+
+impl PerftOptions {
+    pub fn new() -> Self {
+        PerftOptions {
+            threads: 1,
+            hash_mb: 0,
+            table: Arc::default(),
+        }
+    }
+
+    pub fn set_hash(&mut self, mb: usize) {
+        if mb != self.hash_mb {
+            self.hash_mb = mb;
+            self.table = Arc::default();
+        }
+    }
+
+    fn table(&self) -> Option<&PerftTable> {
+        (self.hash_mb > 0).then(|| self.table.get_or_init(|| PerftTable::new(self.hash_mb)))
+    }
+
+    // The node count below each root move, with the root moves spread over the threads.
+    // Nothing is played at depth 0, so its divide is empty.
+    pub fn divide(&self, board: &Board, depth: u8) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut moves = Vec::new();
+        board.generate_moves(|mvs| {
+            moves.extend(mvs);
+            false
+        });
+
+        let count = |mv: Move| {
+            let mut board = board.clone();
+            board.play_unchecked(mv);
+            match self.table() {
+                Some(table) => perft_hashed(&board, depth - 1, table),
+                None => perft(&board, depth - 1),
+            }
+        };
+
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, u64)> = thread::scope(|s| {
+            let handles: Vec<_> = (0..self.threads.clamp(1, moves.len().max(1)))
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&mv) = moves.get(index) else {
+                                return results;
+                            };
+                            results.push((index, count(mv)));
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+
+        results.sort_unstable();
+        results
+            .into_iter()
+            .map(|(index, nodes)| (moves[index], nodes))
+            .collect()
+    }
+
+    pub fn perft(&self, board: &Board, depth: u8) -> u64 {
+        match depth {
+            0 => 1,
+            1 => perft(board, 1),
+            _ => self
+                .divide(board, depth)
+                .iter()
+                .map(|&(_, nodes)| nodes)
+                .sum(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::uci::bench::FENS;
 
     #[test]
     fn perft_startpos() {
@@ -35,5 +314,90 @@ mod tests {
         assert_eq!(perft(&board, 2), 400);
         assert_eq!(perft(&board, 3), 8902);
         assert_eq!(perft(&board, 4), 197281);
+        assert!(PerftOptions::new().divide(&board, 0).is_empty());
+    }
+
+    #[test]
+    fn threaded_and_hashed() {
+        let plain = PerftOptions::new();
+        let mut threaded = PerftOptions::new();
+        threaded.threads = 4;
+        let mut hashed = PerftOptions::new();
+        hashed.threads = 3;
+        hashed.set_hash(1);
+        // A single entry, so almost every probe is a collision.
+        let tiny = PerftOptions {
+            threads: 2,
+            hash_mb: 1,
+            table: Arc::new(OnceLock::from(PerftTable {
+                entries: vec![[AtomicU64::new(0), AtomicU64::new(0)]],
+            })),
+        };
+
+        for fen in FENS.iter().take(10) {
+            let board: Board = fen.parse().unwrap();
+            let expected = perft(&board, 3);
+            let divided = plain.divide(&board, 3);
+
+            assert_eq!(plain.perft(&board, 3), expected);
+            for options in [&threaded, &hashed, &tiny] {
+                assert_eq!(options.perft(&board, 3), expected, "{fen}");
+                assert_eq!(options.divide(&board, 3), divided, "{fen}");
+            }
+        }
+
+        // Twice, so the second run is answered from the table.
+        let board = Board::startpos();
+        assert_eq!(hashed.perft(&board, 5), 4865609);
+        assert_eq!(hashed.perft(&board, 5), 4865609);
+    }
+
+    #[test]
+    fn detailed() {
+        // From https://www.chessprogramming.org/Perft_Results
+        let tests: [(&str, u8, PerftStats); 3] = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4,
+                PerftStats {
+                    nodes: 197281,
+                    captures: 1576,
+                    checks: 469,
+                    checkmates: 8,
+                    ..Default::default()
+                },
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                3,
+                PerftStats {
+                    nodes: 97862,
+                    captures: 17102,
+                    en_passant: 45,
+                    castles: 3162,
+                    checks: 993,
+                    checkmates: 1,
+                    ..Default::default()
+                },
+            ),
+            (
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                5,
+                PerftStats {
+                    nodes: 674624,
+                    captures: 52051,
+                    en_passant: 1165,
+                    checks: 52950,
+                    discovered_checks: 1292,
+                    double_checks: 3,
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        for (fen, depth, expected) in tests {
+            let board = Board::from_fen(fen, false).unwrap();
+            assert_eq!(perft_detailed(&board, depth), expected, "{fen}");
+        }
     }
 }
@@ -0,0 +1,474 @@
+use super::parse_move::{move_to_san, parse_san, SanError};
+use cozy_chess::{Board, Color, Move};
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWin,
+    BlackWin,
+    Draw,
+}
+
+impl GameResult {
+    fn parse(s: &str) -> Option<GameResult> {
+        match s {
+            "1-0" => Some(GameResult::WhiteWin),
+            "0-1" => Some(GameResult::BlackWin),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+}
+
+pub fn result_str(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWin) => "1-0",
+        Some(GameResult::BlackWin) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<Move>,
+    // None for unfinished games (`*`).
+    pub result: Option<GameResult>,
+}
+
+impl Game {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // The starting position followed by the position after every move.
+    pub fn boards(&self) -> impl Iterator<Item = Board> + '_ {
+        let mut board = self.start.clone();
+
+        std::iter::once(board.clone()).chain(self.moves.iter().map(move |&mv| {
+            board.play_unchecked(mv);
+            board.clone()
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    InvalidFen(String),
+    BadMove {
+        ply: usize,
+        san: String,
+        error: SanError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Io(e) => write!(f, "{e}"),
+            PgnError::InvalidFen(fen) => write!(f, "invalid FEN tag {fen}"),
+            PgnError::BadMove { ply, san, error } => {
+                write!(f, "{error:?} move {san} at ply {ply}")
+            }
+        }
+    }
+}
+
+// The unparsed contents of one game.
+#[derive(Default)]
+struct RawGame {
+    tags: Vec<(String, String)>,
+    sans: Vec<String>,
+    result: Option<GameResult>,
+    finished: bool,
+    // Inside a `{}` comment, which can span lines.
+    comment: bool,
+    variation_depth: usize,
+}
+
+impl RawGame {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.sans.is_empty() && !self.finished
+    }
+
+    fn in_movetext(&self) -> bool {
+        !self.sans.is_empty() || self.finished
+    }
+
+    fn push_tag(&mut self, line: &str) {
+        let line = line.trim().trim_start_matches('[').trim_end_matches(']');
+        let Some((name, value)) = line.split_once(char::is_whitespace) else {
+            return;
+        };
+        let value = value.trim().trim_matches('"');
+        self.tags.push((
+            name.to_string(),
+            value.replace("\\\"", "\"").replace("\\\\", "\\"),
+        ));
+    }
+
+    fn push_token(&mut self, token: &str) {
+        if token.is_empty() || self.variation_depth > 0 || self.finished {
+            return;
+        }
+
+        self.result = match token {
+            "1-0" | "0-1" | "1/2-1/2" => GameResult::parse(token),
+            "*" => None,
+            _ => {
+                // Move numbers (`12.` or `12...`), NAGs and annotations (`!?`)
+                let san = match token.find('.') {
+                    Some(i) if token[..i].chars().all(|c| c.is_ascii_digit()) => {
+                        token[i..].trim_start_matches('.')
+                    }
+                    _ => token,
+                };
+                let san = san.trim_end_matches(['!', '?']);
+                if !san.is_empty()
+                    && !san.starts_with('$')
+                    && !san.chars().all(|c| c.is_ascii_digit())
+                {
+                    self.sans.push(san.to_string());
+                }
+                return;
+            }
+        };
+        self.finished = true;
+    }
+
+    fn push_movetext(&mut self, line: &str) {
+        let mut token = String::new();
+
+        for c in line.chars() {
+            if self.comment {
+                self.comment = c != '}';
+                continue;
+            }
+
+            match c {
+                '{' | '(' | ')' | ';' => {
+                    self.push_token(&token);
+                    token.clear();
+                    match c {
+                        '{' => self.comment = true,
+                        '(' => self.variation_depth += 1,
+                        ')' => self.variation_depth = self.variation_depth.saturating_sub(1),
+                        // The rest of the line is a comment.
+                        _ => return,
+                    }
+                }
+                c if c.is_whitespace() => {
+                    self.push_token(&token);
+                    token.clear();
+                }
+                c => token.push(c),
+            }
+        }
+
+        self.push_token(&token);
+    }
+
+    fn into_game(self) -> Result<Game, PgnError> {
+        let mut board = match self.tags.iter().find(|(tag, _)| tag == "FEN") {
+            Some((_, fen)) => Board::from_fen(fen, false)
+                .or_else(|_| Board::from_fen(fen, true))
+                .map_err(|_| PgnError::InvalidFen(fen.clone()))?,
+            None => Board::startpos(),
+        };
+        let start = board.clone();
+
+        let mut moves = Vec::with_capacity(self.sans.len());
+        for (ply, san) in self.sans.into_iter().enumerate() {
+            let mv =
+                parse_san(&board, &san).map_err(|error| PgnError::BadMove { ply, san, error })?;
+            board.play_unchecked(mv);
+            moves.push(mv);
+        }
+
+        // Fall back to the Result tag if the movetext was cut short.
+        let result = match self.finished {
+            true => self.result,
+            false => self
+                .tags
+                .iter()
+                .find(|(tag, _)| tag == "Result")
+                .and_then(|(_, result)| GameResult::parse(result)),
+        };
+
+        Ok(Game {
+            tags: self.tags,
+            start,
+            moves,
+            result,
+        })
+    }
+}
+
+// Reads one game at a time, so files of any size can be processed.
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line: String,
+    // The last line read starts the next game.
+    pending: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            reader,
+            line: String::new(),
+            pending: false,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<bool> {
+        if self.pending {
+            self.pending = false;
+            return Ok(true);
+        }
+
+        self.line.clear();
+        Ok(self.reader.read_line(&mut self.line)? > 0)
+    }
+
+    fn read_game(&mut self) -> io::Result<RawGame> {
+        let mut game = RawGame::default();
+
+        while !game.finished && self.read_line()? {
+            let line = self.line.trim_start_matches('\u{feff}');
+
+            if !game.comment && game.variation_depth == 0 && line.starts_with('[') {
+                // A game without a result ends at the next game's tags.
+                if game.in_movetext() {
+                    self.pending = true;
+                    break;
+                }
+                game.push_tag(line);
+            } else if !line.starts_with('%') {
+                game.push_movetext(line);
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_game() {
+            Ok(game) if game.is_empty() => None,
+            Ok(game) => Some(game.into_game()),
+            Err(e) => Some(Err(PgnError::Io(e))),
+        }
+    }
+}
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 6] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+];
+
+// Export format lines are at most 80 characters.
+const MAX_LINE: usize = 79;
+
+pub struct PgnWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> Self {
+        PgnWriter { writer }
+    }
+
+    fn write_tag(&mut self, name: &str, value: &str) -> io::Result<()> {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(self.writer, "[{name} \"{value}\"]")
+    }
+
+    // Writes the Seven Tag Roster first, then the remaining tags and the movetext.
+    pub fn write_game(&mut self, game: &Game) -> io::Result<()> {
+        for (name, default) in SEVEN_TAG_ROSTER {
+            self.write_tag(name, game.tag(name).unwrap_or(default))?;
+        }
+        self.write_tag("Result", result_str(game.result))?;
+
+        if game.start != Board::startpos() {
+            self.write_tag("SetUp", "1")?;
+            self.write_tag("FEN", &game.start.to_string())?;
+        }
+
+        for (name, value) in &game.tags {
+            let written = SEVEN_TAG_ROSTER.iter().any(|&(tag, _)| tag == name);
+            if !written && !matches!(name.as_str(), "Result" | "SetUp" | "FEN") {
+                self.write_tag(name, value)?;
+            }
+        }
+        writeln!(self.writer)?;
+
+        let mut tokens = Vec::with_capacity(game.moves.len() * 3 / 2 + 1);
+        for (ply, (board, &mv)) in game.boards().zip(&game.moves).enumerate() {
+            let number = board.fullmove_number();
+            match board.side_to_move() {
+                Color::White => tokens.push(format!("{number}.")),
+                Color::Black if ply == 0 => tokens.push(format!("{number}...")),
+                Color::Black => {}
+            }
+            tokens.push(move_to_san(&board, mv));
+        }
+        tokens.push(result_str(game.result).to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE {
+                writeln!(self.writer, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(self.writer, "{line}")?;
+        writeln!(self.writer)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGN: &str = r#"[Event "Test"]
+[White "A \"quoted\" name"]
+
+1. e4 {a comment
+over two lines} e5 2. Nf3 $1 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6!? ; rest of line
+3. Bb5 a6 1-0
+
+[Event "No result"]
+[FEN "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"]
+
+1. O-O Kd7
+
+[Event "Broken"]
+
+1. e4 Ke7 2. Nf3 *
+"#;
+
+    #[test]
+    fn read_games() {
+        let games: Vec<_> = PgnReader::new(PGN.as_bytes()).collect();
+        assert_eq!(games.len(), 3);
+
+        let game = games[0].as_ref().unwrap();
+        assert_eq!(game.tag("White"), Some("A \"quoted\" name"));
+        assert_eq!(game.result, Some(GameResult::WhiteWin));
+        let moves: Vec<String> = game.moves.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]);
+
+        let game = games[1].as_ref().unwrap();
+        assert_eq!(game.result, None);
+        assert_eq!(
+            game.moves,
+            ["e1h1".parse().unwrap(), "e8d7".parse().unwrap()]
+        );
+
+        assert!(matches!(
+            games[2],
+            Err(PgnError::BadMove {
+                ply: 1,
+                error: SanError::Illegal,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn boards() {
+        let game = PgnReader::new(PGN.as_bytes()).next().unwrap().unwrap();
+        let boards: Vec<Board> = game.boards().collect();
+
+        assert_eq!(boards.len(), game.moves.len() + 1);
+        assert_eq!(boards[0], Board::startpos());
+        assert_eq!(
+            boards[6].to_string(),
+            "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4"
+        );
+    }
+
+    #[test]
+    fn write_games() {
+        let mut out = Vec::new();
+        let mut writer = PgnWriter::new(&mut out);
+        let games: Vec<Game> = PgnReader::new(PGN.as_bytes())
+            .filter_map(Result::ok)
+            .collect();
+        for game in &games {
+            writer.write_game(game).unwrap();
+        }
+
+        let pgn = String::from_utf8(out).unwrap();
+        assert!(pgn.starts_with("[Event \"Test\"]\n[Site \"?\"]\n"));
+        assert!(pgn.contains("[White \"A \\\"quoted\\\" name\"]\n"));
+        assert!(pgn.contains("\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0\n"));
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1\"]\n"));
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+
+        // Reading the output back gives the same games.
+        let reread: Vec<Game> = PgnReader::new(pgn.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(reread.len(), games.len());
+        for (a, b) in games.iter().zip(&reread) {
+            assert_eq!(
+                (&a.start, &a.moves, a.result),
+                (&b.start, &b.moves, b.result)
+            );
+            assert_eq!(a.tag("Event"), b.tag("Event"));
+        }
+    }
+
+    #[test]
+    fn black_to_move_and_wrapping() {
+        let start: Board = "4k3/8/8/8/8/8/8/4K3 b - - 0 30".parse().unwrap();
+        let mut moves = Vec::new();
+        let mut board = start.clone();
+        for mv in ["e8d7", "e1d2", "d7e8", "d2e1"].iter().cycle().take(40) {
+            let mv = mv.parse().unwrap();
+            board.play(mv);
+            moves.push(mv);
+        }
+        let game = Game {
+            tags: Vec::new(),
+            start,
+            moves,
+            result: Some(GameResult::Draw),
+        };
+
+        let mut out = Vec::new();
+        PgnWriter::new(&mut out).write_game(&game).unwrap();
+        let pgn = String::from_utf8(out).unwrap();
+
+        assert!(pgn.contains("\n30... Kd7 31. Kd2 Ke8 32. Ke1 "));
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+        assert!(pgn.trim_end().ends_with("1/2-1/2"));
+
+        let reread = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(reread.moves, game.moves);
+    }
+}
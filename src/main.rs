@@ -1,21 +1,75 @@
 use std::io;
+mod book;
 mod chess;
 mod search;
+mod tools;
+#[cfg(feature = "tuner")]
+mod tuner;
 mod uci;
+mod xboard;
 
 fn main() -> io::Result<()> {
-    if std::env::args().nth(1).as_deref() == Some("bench") {
-        crate::uci::bench::bench();
-        return Ok(());
-    }
-
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => ("", &[][..]),
+    };
 
-    match input.as_str().trim() {
-        "uci" => crate::uci::listen::listen(),
-        _ => println!("Unknown protocol"),
+    // Tools that can fail return false, which makes the exit code 1.
+    let success = match command {
+        "bench" => {
+            crate::uci::bench::bench();
+            true
+        }
+        #[cfg(feature = "tuner")]
+        "tune" => {
+            crate::tuner::tune(args);
+            true
+        }
+        "search" => crate::tools::cli::search(args),
+        "perft" => crate::tools::cli::perft(args),
+        "eval" => crate::tools::cli::eval(args),
+        "data" => {
+            crate::tools::data::data(args);
+            true
+        }
+        "datagen" => {
+            crate::tools::datagen::datagen(args);
+            true
+        }
+        "epd" => {
+            crate::tools::epd::epd(args);
+            true
+        }
+        "makebook" => {
+            crate::tools::makebook::makebook(args);
+            true
+        }
+        "match" => {
+            crate::tools::matches::matches(args);
+            true
+        }
+        "perftdiff" => crate::tools::perftdiff::perftdiff(args),
+        "perftsuite" => crate::tools::perftsuite::perftsuite(args),
+        "symmetry" => crate::tools::symmetry::symmetry(args.first().map(String::as_str)),
+        "" => {
+            crate::uci::listen::listen();
+            true
+        }
+        command => {
+            println!("Unknown subcommand {command}");
+            let tune = if cfg!(feature = "tuner") {
+                " | tune"
+            } else {
+                ""
+            };
+            println!("Usage: honse [bench | search | perft | eval | data | datagen | epd | makebook | match | perftdiff | perftsuite | symmetry{tune}]");
+            false
+        }
     };
 
+    if !success {
+        std::process::exit(1);
+    }
     Ok(())
 }
@@ -1,3 +1,11 @@
 pub const MAX_PLY: u8 = 128;
 pub const INFINITY: i16 = 32_001;
 pub const MATE: i16 = 32_000;
+
+// Moves to mate for mate scores, negative when getting mated
+pub fn moves_to_mate(score: i16) -> Option<i32> {
+    let plies = MATE - score.abs();
+    (0..MAX_PLY as i16)
+        .contains(&plies)
+        .then(|| score.signum() as i32 * (plies as i32 + 1) / 2)
+}
@@ -1,43 +1,144 @@
-use super::evaluation::psts::*;
-use cozy_chess::{Board, Color};
+use super::{
+    evaluation::{
+        params,
+        pawns::{evaluate_pawns, PawnEntry},
+        psts::*,
+        trace::{EvalTrace, NoTrace, Term, Trace},
+    },
+    pawn_table::PawnTable,
+};
+use cozy_chess::{BitBoard, Board, Color, Piece};
 use once_cell::sync::Lazy;
 
 pub const PHASE_VALUES: [i32; 6] = [0, 1, 1, 2, 4, 0];
 static PST: Lazy<PieceSquareTable> = Lazy::new(PieceSquareTable::new);
 
 #[must_use]
-pub fn eval(board: &Board) -> i16 {
+pub fn eval(board: &Board, pawn_table: &mut PawnTable) -> i16 {
+    let pawns = pawn_table.probe(board);
+    let score = evaluate(board, &pawns, &mut NoTrace);
+
+    match board.side_to_move() {
+        Color::White => score as i16,
+        Color::Black => -score as i16,
+    }
+}
+
+// Evaluates without the pawn hash table and records the contribution of every term.
+pub fn eval_trace(board: &Board) -> EvalTrace {
+    let mut trace = EvalTrace::new(board.side_to_move());
+    trace.score = traced(board, &mut trace);
+    trace
+}
+
+// Returns the tapered score from white's point of view.
+pub fn traced<T: Trace>(board: &Board, trace: &mut T) -> i32 {
+    let pawns = evaluate_pawns(board, trace);
+    evaluate(board, &pawns, trace)
+}
+
+pub fn game_phase(board: &Board) -> i32 {
+    board
+        .occupied()
+        .into_iter()
+        .map(|square| PHASE_VALUES[board.piece_on(square).unwrap() as usize])
+        .sum()
+}
+
+// Returns the tapered score from white's point of view.
+fn evaluate<T: Trace>(board: &Board, pawns: &PawnEntry, trace: &mut T) -> i32 {
     let mut mg = 0;
     let mut eg = 0;
-    let mut game_phase = 0;
 
     for square in board.occupied() {
-        let piece = board.piece_on(square).unwrap() as usize;
-        let color = board.color_on(square).unwrap() as usize;
-        let sq = square as usize;
-
-        game_phase += PHASE_VALUES[piece];
+        let piece = board.piece_on(square).unwrap();
+        let color = board.color_on(square).unwrap();
+        let (index, sq) = (color as usize + piece as usize * 2, square as usize);
 
         // PST contains material value.
-        mg += PST.mg_pst[color + piece * 2][sq];
-        eg += PST.eg_pst[color + piece * 2][sq];
+        mg += PST.mg_pst[index][sq];
+        eg += PST.eg_pst[index][sq];
+
+        let (mg_value, eg_value) = (
+            MG_PIECE_VALUES[piece as usize],
+            EG_PIECE_VALUES[piece as usize],
+        );
+        let (mg_pst, eg_pst) = match color {
+            Color::White => (PST.mg_pst[index][sq], PST.eg_pst[index][sq]),
+            Color::Black => (-PST.mg_pst[index][sq], -PST.eg_pst[index][sq]),
+        };
+        trace.add(Term::Material, color, mg_value, eg_value);
+        trace.add(Term::Psts, color, mg_pst - mg_value, eg_pst - eg_value);
+
+        let pst_square = match color {
+            Color::White => sq ^ 56,
+            Color::Black => sq,
+        };
+        trace.coeff(params::PIECE_VALUE + piece as usize, color, 1);
+        trace.coeff(params::PST + piece as usize * 64 + pst_square, color, 1);
     }
 
-    let mg_weight = game_phase.min(24);
-    let eg_weight = 24 - mg_weight;
+    mg += pawns.mg;
+    eg += pawns.eg;
 
-    let score = ((mg * mg_weight) + (eg * eg_weight)) / 24;
+    for color in Color::ALL {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
 
-    match board.side_to_move() {
-        Color::White => score as i16,
-        Color::Black => -score as i16,
+        // Pieces other than pawns and the king attacked by enemy pawns
+        let targets = board.colors(color) & !board.pieces(Piece::Pawn) & !board.pieces(Piece::King);
+        let threats = (pawns.attacks[!color as usize] & targets).len() as i32;
+        mg -= sign * threats * MG_PAWN_THREAT;
+        eg -= sign * threats * EG_PAWN_THREAT;
+        trace.add(
+            Term::PawnThreats,
+            !color,
+            threats * MG_PAWN_THREAT,
+            threats * EG_PAWN_THREAT,
+        );
+        trace.coeff(params::PAWN_THREAT, !color, threats);
+
+        // Passed pawns with a piece standing right in front of them
+        let passed = pawns.passed[color as usize].0;
+        let stops = match color {
+            Color::White => passed << 8,
+            Color::Black => passed >> 8,
+        };
+        let blocked = (BitBoard(stops) & board.occupied()).len() as i32;
+        mg += sign * blocked * MG_BLOCKED_PASSER;
+        eg += sign * blocked * EG_BLOCKED_PASSER;
+        trace.add(
+            Term::BlockedPassers,
+            color,
+            blocked * MG_BLOCKED_PASSER,
+            blocked * EG_BLOCKED_PASSER,
+        );
+        trace.coeff(params::BLOCKED_PASSER, color, blocked);
     }
+
+    let mg_weight = game_phase(board).min(24);
+    let eg_weight = 24 - mg_weight;
+    trace.set_phase(mg_weight);
+
+    ((mg * mg_weight) + (eg * eg_weight)) / 24
 }
 
 #[cfg(test)]
 mod test {
-    use super::eval;
-    use cozy_chess::Board;
+    use super::{eval, eval_trace};
+    use crate::{
+        search::{
+            evaluation::{
+                psts::{EG_PAWN_THREAT, MG_PAWN_THREAT},
+                trace::Term,
+            },
+            pawn_table::PawnTable,
+        },
+        uci::bench::FENS,
+    };
+    use cozy_chess::{Board, Color};
 
     #[test]
     fn eval_sanity() {
@@ -47,6 +148,42 @@ mod test {
         )
         .unwrap();
 
-        assert!(eval(&board) > 0)
+        assert!(eval(&board, &mut PawnTable::new(1)) > 0)
+    }
+
+    #[test]
+    fn pawn_threats() {
+        // The pawn on d2 attacks the knight on c1 and checks the king on e1.
+        let board: Board = "4k3/8/8/8/8/8/3p4/2N1K3 w - - 0 1".parse().unwrap();
+        let trace = eval_trace(&board);
+        assert_eq!(
+            trace.term(Term::PawnThreats, Color::Black),
+            (MG_PAWN_THREAT, EG_PAWN_THREAT)
+        );
+        assert_eq!(trace.term(Term::PawnThreats, Color::White), (0, 0));
+    }
+
+    #[test]
+    fn trace_matches_eval() {
+        let mut pawn_table = PawnTable::new(1);
+
+        for fen in FENS {
+            let board = Board::from_fen(fen, false).unwrap();
+            let trace = eval_trace(&board);
+            let stm = match board.side_to_move() {
+                Color::White => trace.score,
+                Color::Black => -trace.score,
+            };
+
+            assert_eq!(stm as i16, eval(&board, &mut pawn_table));
+
+            // The terms add up to the final score
+            let (mg, eg) = Term::ALL.iter().fold((0, 0), |(mg, eg), &term| {
+                let (term_mg, term_eg) = trace.total(term);
+                (mg + term_mg, eg + term_eg)
+            });
+            let tapered = (mg * trace.phase + eg * (24 - trace.phase)) / 24;
+            assert_eq!(tapered, trace.score);
+        }
     }
 }
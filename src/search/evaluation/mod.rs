@@ -1 +1,6 @@
+#[cfg(feature = "nnue")]
+pub mod nnue;
+pub mod params;
+pub mod pawns;
 pub mod psts;
+pub mod trace;
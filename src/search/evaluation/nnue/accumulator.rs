@@ -0,0 +1,173 @@
+use super::{
+    network::{feature, Network, HIDDEN, QA, QB, SCALE},
+    simd::{self, Simd, SIMD},
+};
+use cozy_chess::{Board, Color, File, Move, Piece, Square};
+
+// Hidden layer values before activation, indexed by perspective.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Accumulator {
+    pub values: [[i16; HIDDEN]; 2],
+}
+
+impl Accumulator {
+    pub fn refresh(network: &Network, board: &Board) -> Self {
+        // Safety: `SIMD` is what this CPU supports.
+        unsafe { Self::refresh_with(*SIMD, network, board) }
+    }
+
+    // Safety: the CPU must support `simd`, see `simd::add_assign_with`.
+    pub unsafe fn refresh_with(simd: Simd, network: &Network, board: &Board) -> Self {
+        let mut acc = Accumulator {
+            values: [network.feature_bias; 2],
+        };
+
+        for square in board.occupied() {
+            let piece = board.piece_on(square).unwrap();
+            let color = board.color_on(square).unwrap();
+            acc.add(simd, network, piece, color, square);
+        }
+
+        acc
+    }
+
+    unsafe fn add(
+        &mut self,
+        simd: Simd,
+        network: &Network,
+        piece: Piece,
+        color: Color,
+        square: Square,
+    ) {
+        for perspective in Color::ALL {
+            let weights = &network.feature_weights[feature(perspective, piece, color, square)];
+            simd::add_assign_with(simd, &mut self.values[perspective as usize], weights);
+        }
+    }
+
+    unsafe fn sub(
+        &mut self,
+        simd: Simd,
+        network: &Network,
+        piece: Piece,
+        color: Color,
+        square: Square,
+    ) {
+        for perspective in Color::ALL {
+            let weights = &network.feature_weights[feature(perspective, piece, color, square)];
+            simd::sub_assign_with(simd, &mut self.values[perspective as usize], weights);
+        }
+    }
+
+    // Returns the accumulator after `mv` is played on `board`.
+    pub fn update(&self, network: &Network, board: &Board, mv: Move) -> Self {
+        // Safety: as in `refresh`.
+        unsafe { self.update_with(*SIMD, network, board, mv) }
+    }
+
+    // Safety: as for `refresh_with`.
+    pub unsafe fn update_with(
+        &self,
+        simd: Simd,
+        network: &Network,
+        board: &Board,
+        mv: Move,
+    ) -> Self {
+        let mut acc = *self;
+        let us = board.side_to_move();
+        let piece = board.piece_on(mv.from).unwrap();
+
+        // Castling is encoded as the king capturing its own rook.
+        if piece == Piece::King && board.color_on(mv.to) == Some(us) {
+            let rank = mv.from.rank();
+            let (king, rook) = if mv.to.file() > mv.from.file() {
+                (File::G, File::F)
+            } else {
+                (File::C, File::D)
+            };
+
+            acc.sub(simd, network, Piece::King, us, mv.from);
+            acc.sub(simd, network, Piece::Rook, us, mv.to);
+            acc.add(simd, network, Piece::King, us, Square::new(king, rank));
+            acc.add(simd, network, Piece::Rook, us, Square::new(rook, rank));
+            return acc;
+        }
+
+        if let Some(captured) = board.piece_on(mv.to) {
+            acc.sub(simd, network, captured, !us, mv.to);
+        } else if piece == Piece::Pawn && mv.from.file() != mv.to.file() {
+            // En passant
+            let victim = Square::new(mv.to.file(), mv.from.rank());
+            acc.sub(simd, network, Piece::Pawn, !us, victim);
+        }
+
+        acc.sub(simd, network, piece, us, mv.from);
+        acc.add(simd, network, mv.promotion.unwrap_or(piece), us, mv.to);
+        acc
+    }
+
+    // Returns the evaluation in centipawns from the side to move's point of view.
+    pub fn evaluate(&self, network: &Network, side_to_move: Color) -> i32 {
+        // Safety: as in `refresh`.
+        unsafe { self.evaluate_with(*SIMD, network, side_to_move) }
+    }
+
+    // Safety: as for `refresh_with`.
+    pub unsafe fn evaluate_with(&self, simd: Simd, network: &Network, side_to_move: Color) -> i32 {
+        let us = &self.values[side_to_move as usize];
+        let them = &self.values[!side_to_move as usize];
+
+        let output = network.output_bias as i32 * QA
+            + simd::crelu_dot_with(simd, us, &network.output_weights[0])
+            + simd::crelu_dot_with(simd, them, &network.output_weights[1]);
+
+        output * SCALE / (QA * QB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chess::move_gen::all_moves, uci::bench::FENS};
+
+    #[test]
+    fn incremental_matches_refresh() {
+        let network = Network::default_net();
+        let mut fens = FENS.to_vec();
+        fens.extend([
+            // Castling, en passant and promotions
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "1r2k2r/8/8/8/8/8/8/R3K1R1 w GAhb - 0 1",
+        ]);
+
+        for fen in fens {
+            let board: Board = fen.parse().unwrap();
+            let acc = Accumulator::refresh(&network, &board);
+
+            for mv in all_moves(&board) {
+                let mut child = board.clone();
+                child.play_unchecked(mv);
+
+                assert_eq!(
+                    acc.update(&network, &board, mv),
+                    Accumulator::refresh(&network, &child),
+                    "{fen} {mv}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_net_sanity() {
+        let network = Network::default_net();
+        let board: Board = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let acc = Accumulator::refresh(&network, &board);
+
+        assert!(acc.evaluate(&network, Color::White) > 500);
+        assert!(acc.evaluate(&network, Color::Black) < -500);
+    }
+}
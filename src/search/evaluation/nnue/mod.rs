@@ -0,0 +1,71 @@
+pub mod accumulator;
+pub mod network;
+pub mod simd;
+
+use crate::search::definitions::MAX_PLY;
+use accumulator::Accumulator;
+use cozy_chess::{Board, Move};
+use network::Network;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+// The network used by new searches. `EvalFile` replaces it.
+static NETWORK: Lazy<RwLock<Arc<Network>>> =
+    Lazy::new(|| RwLock::new(Arc::from(Network::default_net())));
+
+pub fn network() -> Arc<Network> {
+    NETWORK.read().unwrap().clone()
+}
+
+pub fn load_network(path: &str) -> Result<(), String> {
+    let network = match path {
+        "" | "<default>" => Network::default_net(),
+        _ => {
+            let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+            Network::from_bytes(&bytes).map_err(|e| e.to_string())?
+        }
+    };
+
+    *NETWORK.write().unwrap() = Arc::from(network);
+    Ok(())
+}
+
+// Keeps one accumulator per ply, so moves can be taken back for free.
+pub struct Nnue {
+    network: Arc<Network>,
+    stack: Vec<Accumulator>,
+}
+
+impl Nnue {
+    pub fn new() -> Self {
+        Nnue {
+            network: network(),
+            stack: Vec::with_capacity(MAX_PLY as usize + 1),
+        }
+    }
+
+    pub fn reset(&mut self, board: &Board) {
+        self.network = network();
+        self.stack.clear();
+        self.stack.push(Accumulator::refresh(&self.network, board));
+    }
+
+    pub fn push(&mut self, board: &Board, mv: Move) {
+        let acc = self.stack.last().unwrap().update(&self.network, board, mv);
+        self.stack.push(acc);
+    }
+
+    pub fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    pub fn evaluate(&self, board: &Board) -> i16 {
+        let score = self
+            .stack
+            .last()
+            .unwrap()
+            .evaluate(&self.network, board.side_to_move());
+
+        score.clamp(-30_000, 30_000) as i16
+    }
+}
@@ -0,0 +1,246 @@
+use cozy_chess::{Color, Piece, Square};
+use std::fmt;
+
+pub const INPUTS: usize = 768;
+pub const HIDDEN: usize = 64;
+
+// Quantization of the feature transformer and the output layer.
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"HNSE";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 12;
+
+// Encodes the PeSTO tables, until we have a trained net. Generated by `pesto_net`
+// in the tests below, which also checks it against the tables.
+static DEFAULT_NET: &[u8] =
+    include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/nets/default.nnue"));
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    WrongHiddenSize(u32),
+    WrongLength(usize),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::BadMagic => write!(f, "not a honse network file"),
+            NetworkError::UnsupportedVersion(v) => {
+                write!(f, "unsupported network version {v}, expected {VERSION}")
+            }
+            NetworkError::WrongHiddenSize(n) => {
+                write!(f, "hidden layer has {n} neurons, expected {HIDDEN}")
+            }
+            NetworkError::WrongLength(n) => write!(f, "unexpected file length {n}"),
+        }
+    }
+}
+
+// (768 -> HIDDEN)x2 -> 1, with the side to move's half of the hidden layer first.
+pub struct Network {
+    pub feature_weights: Vec<[i16; HIDDEN]>,
+    pub feature_bias: [i16; HIDDEN],
+    pub output_weights: [[i16; HIDDEN]; 2],
+    pub output_bias: i16,
+}
+
+impl Network {
+    pub const SIZE: usize = HEADER_SIZE + 2 * (INPUTS * HIDDEN + HIDDEN + 2 * HIDDEN + 1);
+
+    // File layout, all little endian: "HNSE", version (u32), hidden size (u32),
+    // feature weights, feature bias, output weights (us, then them) and output bias as i16.
+    // The output bias is quantized by QB alone.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Box<Network>, NetworkError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err(NetworkError::BadMagic);
+        }
+
+        let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        match (read_u32(4), read_u32(8)) {
+            (VERSION, n) if n as usize == HIDDEN => {}
+            (VERSION, n) => return Err(NetworkError::WrongHiddenSize(n)),
+            (v, _) => return Err(NetworkError::UnsupportedVersion(v)),
+        }
+
+        if bytes.len() != Self::SIZE {
+            return Err(NetworkError::WrongLength(bytes.len()));
+        }
+
+        let mut values = bytes[HEADER_SIZE..]
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]));
+        let mut next = || values.next().unwrap();
+
+        let mut network = Box::new(Network {
+            feature_weights: vec![[0; HIDDEN]; INPUTS],
+            feature_bias: [0; HIDDEN],
+            output_weights: [[0; HIDDEN]; 2],
+            output_bias: 0,
+        });
+
+        for weights in network.feature_weights.iter_mut() {
+            weights.iter_mut().for_each(|w| *w = next());
+        }
+        network.feature_bias.iter_mut().for_each(|w| *w = next());
+        for weights in network.output_weights.iter_mut() {
+            weights.iter_mut().for_each(|w| *w = next());
+        }
+        network.output_bias = next();
+
+        Ok(network)
+    }
+
+    pub fn default_net() -> Box<Network> {
+        Network::from_bytes(DEFAULT_NET).expect("embedded network is valid")
+    }
+}
+
+// Index of a piece in the input layer, as seen from `perspective`.
+pub fn feature(perspective: Color, piece: Piece, color: Color, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (color as usize, square as usize),
+        Color::Black => ((!color) as usize, square as usize ^ 56),
+    };
+
+    side * 384 + piece as usize * 64 + square
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        search::evaluation::{
+            nnue::accumulator::Accumulator,
+            psts::{EG_PIECE_VALUES, EG_PST, MG_PIECE_VALUES, MG_PST},
+        },
+        uci::bench::FENS,
+    };
+    use cozy_chess::Board;
+
+    // Each piece's own square gets a hidden neuron holding its value averaged over
+    // the middlegame and endgame tables, divided by 6 to stay below QA. The output
+    // adds our neurons and subtracts theirs.
+    fn pesto_net() -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((HIDDEN as u32).to_le_bytes());
+
+        let mut weights = vec![[0i16; HIDDEN]; INPUTS];
+        for piece in 0..6 {
+            for square in 0..64 {
+                weights[piece * 64 + square][square] =
+                    (pesto_value(piece, square) as f64 / 6.0).round() as i16;
+            }
+        }
+
+        let values = weights.iter().flatten().copied();
+        let feature_bias = [0i16; HIDDEN];
+        let output_weights = [245i16; HIDDEN].into_iter().chain([-245; HIDDEN]);
+        let output_bias = 0i16;
+        for value in values
+            .chain(feature_bias)
+            .chain(output_weights)
+            .chain([output_bias])
+        {
+            bytes.extend(value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    // The averaged value of a piece on `square`, seen from its own side. Kings are
+    // offset to stay clear of the CReLU at 0, which cancels out between the sides.
+    fn pesto_value(piece: usize, square: usize) -> i32 {
+        let mg = MG_PIECE_VALUES[piece] + MG_PST[piece][square ^ 56];
+        let eg = EG_PIECE_VALUES[piece] + EG_PST[piece][square ^ 56];
+        let offset = if piece == Piece::King as usize {
+            100
+        } else {
+            0
+        };
+        ((mg + eg) / 2 + offset).max(0)
+    }
+
+    #[test]
+    fn default_net_is_pesto() {
+        // `HONSE_WRITE_NET=1 cargo test --features nnue default_net` regenerates
+        // the file after the tables change.
+        if std::env::var_os("HONSE_WRITE_NET").is_some() {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/nets/default.nnue");
+            std::fs::write(path, pesto_net()).unwrap();
+            return;
+        }
+
+        assert!(
+            pesto_net() == DEFAULT_NET,
+            "nets/default.nnue is out of date"
+        );
+
+        // The output is the averaged PeSTO material and PST balance.
+        let network = Network::default_net();
+        for fen in FENS {
+            let board: Board = fen.parse().unwrap();
+            let expected: i32 = board
+                .occupied()
+                .into_iter()
+                .map(|square| {
+                    let piece = board.piece_on(square).unwrap() as usize;
+                    let color = board.color_on(square).unwrap();
+                    let relative = match color {
+                        Color::White => square as usize,
+                        Color::Black => square as usize ^ 56,
+                    };
+                    let value = pesto_value(piece, relative);
+                    if color == board.side_to_move() {
+                        value
+                    } else {
+                        -value
+                    }
+                })
+                .sum();
+
+            let score =
+                Accumulator::refresh(&network, &board).evaluate(&network, board.side_to_move());
+            // Rounding to a sixth costs up to 3cp per piece.
+            let tolerance = 3 * board.occupied().len() as i32;
+            assert!(
+                (score - expected).abs() <= tolerance,
+                "{fen}: {score} {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn header() {
+        let mut bytes = DEFAULT_NET.to_vec();
+        assert!(Network::from_bytes(&bytes).is_ok());
+
+        bytes[8] = 32;
+        assert_eq!(
+            Network::from_bytes(&bytes).err(),
+            Some(NetworkError::WrongHiddenSize(32))
+        );
+
+        bytes[4] = 2;
+        assert_eq!(
+            Network::from_bytes(&bytes).err(),
+            Some(NetworkError::UnsupportedVersion(2))
+        );
+
+        bytes[0] = b'X';
+        assert_eq!(
+            Network::from_bytes(&bytes).err(),
+            Some(NetworkError::BadMagic)
+        );
+
+        assert_eq!(
+            Network::from_bytes(&DEFAULT_NET[..100]).err(),
+            Some(NetworkError::WrongLength(100))
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use super::network::{HIDDEN, QA};
+use once_cell::sync::Lazy;
+
+// Instruction sets for the accumulator updates and the output layer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Simd {
+    Avx2,
+    Sse41,
+    Scalar,
+}
+
+// Chosen at compile time when built with e.g. `-C target-cpu=native`,
+// otherwise by checking what the CPU supports on first use.
+pub static SIMD: Lazy<Simd> = Lazy::new(detect);
+
+pub fn detect() -> Simd {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if cfg!(target_feature = "avx2") || is_x86_feature_detected!("avx2") {
+            return Simd::Avx2;
+        }
+        if cfg!(target_feature = "sse4.1") || is_x86_feature_detected!("sse4.1") {
+            return Simd::Sse41;
+        }
+    }
+
+    Simd::Scalar
+}
+
+// Safety: the CPU must support `simd`, which holds for `*SIMD` and the paths
+// below it. The search goes through the safe wrappers in the accumulator.
+#[inline(always)]
+pub unsafe fn add_assign_with(simd: Simd, values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+    match simd {
+        #[cfg(target_arch = "x86_64")]
+        Simd::Avx2 => unsafe { x86::add_assign_avx2(values, weights) },
+        #[cfg(target_arch = "x86_64")]
+        Simd::Sse41 => unsafe { x86::add_assign_sse41(values, weights) },
+        _ => {
+            for (value, &weight) in values.iter_mut().zip(weights) {
+                *value = value.wrapping_add(weight);
+            }
+        }
+    }
+}
+
+// Safety: as for `add_assign_with`.
+#[inline(always)]
+pub unsafe fn sub_assign_with(simd: Simd, values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+    match simd {
+        #[cfg(target_arch = "x86_64")]
+        Simd::Avx2 => unsafe { x86::sub_assign_avx2(values, weights) },
+        #[cfg(target_arch = "x86_64")]
+        Simd::Sse41 => unsafe { x86::sub_assign_sse41(values, weights) },
+        _ => {
+            for (value, &weight) in values.iter_mut().zip(weights) {
+                *value = value.wrapping_sub(weight);
+            }
+        }
+    }
+}
+
+// Sum of clamp(value, 0, QA) * weight.
+// Safety: as for `add_assign_with`.
+#[inline(always)]
+pub unsafe fn crelu_dot_with(simd: Simd, values: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    match simd {
+        #[cfg(target_arch = "x86_64")]
+        Simd::Avx2 => unsafe { x86::crelu_dot_avx2(values, weights) },
+        #[cfg(target_arch = "x86_64")]
+        Simd::Sse41 => unsafe { x86::crelu_dot_sse41(values, weights) },
+        _ => values
+            .iter()
+            .zip(weights)
+            .map(|(&value, &weight)| (value as i32).clamp(0, QA) * weight as i32)
+            .sum(),
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{HIDDEN, QA};
+    use std::arch::x86_64::*;
+
+    const _: () = assert!(HIDDEN.is_multiple_of(16));
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add_assign_avx2(values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+        for i in (0..HIDDEN).step_by(16) {
+            let v = _mm256_loadu_si256(values.as_ptr().add(i).cast());
+            let w = _mm256_loadu_si256(weights.as_ptr().add(i).cast());
+            _mm256_storeu_si256(values.as_mut_ptr().add(i).cast(), _mm256_add_epi16(v, w));
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub_assign_avx2(values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+        for i in (0..HIDDEN).step_by(16) {
+            let v = _mm256_loadu_si256(values.as_ptr().add(i).cast());
+            let w = _mm256_loadu_si256(weights.as_ptr().add(i).cast());
+            _mm256_storeu_si256(values.as_mut_ptr().add(i).cast(), _mm256_sub_epi16(v, w));
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn crelu_dot_avx2(values: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+        let zero = _mm256_setzero_si256();
+        let qa = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+
+        for i in (0..HIDDEN).step_by(16) {
+            let v = _mm256_loadu_si256(values.as_ptr().add(i).cast());
+            let w = _mm256_loadu_si256(weights.as_ptr().add(i).cast());
+            let clipped = _mm256_min_epi16(_mm256_max_epi16(v, zero), qa);
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+        }
+
+        let sum = _mm_add_epi32(
+            _mm256_castsi256_si128(sum),
+            _mm256_extracti128_si256(sum, 1),
+        );
+        horizontal_sum_sse41(sum)
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn add_assign_sse41(values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+        for i in (0..HIDDEN).step_by(8) {
+            let v = _mm_loadu_si128(values.as_ptr().add(i).cast());
+            let w = _mm_loadu_si128(weights.as_ptr().add(i).cast());
+            _mm_storeu_si128(values.as_mut_ptr().add(i).cast(), _mm_add_epi16(v, w));
+        }
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn sub_assign_sse41(values: &mut [i16; HIDDEN], weights: &[i16; HIDDEN]) {
+        for i in (0..HIDDEN).step_by(8) {
+            let v = _mm_loadu_si128(values.as_ptr().add(i).cast());
+            let w = _mm_loadu_si128(weights.as_ptr().add(i).cast());
+            _mm_storeu_si128(values.as_mut_ptr().add(i).cast(), _mm_sub_epi16(v, w));
+        }
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn crelu_dot_sse41(values: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+        let zero = _mm_setzero_si128();
+        let qa = _mm_set1_epi16(QA as i16);
+        let mut sum = _mm_setzero_si128();
+
+        for i in (0..HIDDEN).step_by(8) {
+            let v = _mm_loadu_si128(values.as_ptr().add(i).cast());
+            let w = _mm_loadu_si128(weights.as_ptr().add(i).cast());
+            let clipped = _mm_min_epi16(_mm_max_epi16(v, zero), qa);
+            sum = _mm_add_epi32(sum, _mm_madd_epi16(clipped, w));
+        }
+
+        horizontal_sum_sse41(sum)
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn horizontal_sum_sse41(sum: __m128i) -> i32 {
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b01_00_11_10));
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32(sum, 0b10_11_00_01));
+        _mm_cvtsi128_si32(sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chess::move_gen::all_moves,
+        search::evaluation::nnue::{
+            accumulator::Accumulator,
+            network::{Network, INPUTS},
+        },
+        uci::bench::FENS,
+    };
+    use cozy_chess::{Board, Color};
+
+    // Every path this CPU can run.
+    pub fn supported() -> Vec<Simd> {
+        match detect() {
+            Simd::Avx2 => vec![Simd::Avx2, Simd::Sse41, Simd::Scalar],
+            Simd::Sse41 => vec![Simd::Sse41, Simd::Scalar],
+            Simd::Scalar => vec![Simd::Scalar],
+        }
+    }
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn weight(&mut self) -> i16 {
+            (self.next() % 512) as i16 - 256
+        }
+    }
+
+    fn random_network(rng: &mut XorShift) -> Network {
+        let mut row = || std::array::from_fn(|_| rng.weight());
+        let feature_weights = (0..INPUTS).map(|_| row()).collect();
+
+        Network {
+            feature_weights,
+            feature_bias: row(),
+            output_weights: [row(), row()],
+            output_bias: row()[0],
+        }
+    }
+
+    #[test]
+    fn paths_are_identical() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        let network = random_network(&mut rng);
+
+        for fen in FENS {
+            let mut board: Board = fen.parse().unwrap();
+
+            // Walk a few random moves from every position.
+            for _ in 0..8 {
+                let moves = all_moves(&board);
+                if moves.is_empty() {
+                    break;
+                }
+                let mv = moves[rng.next() as usize % moves.len()];
+
+                let results: Vec<(Accumulator, i32)> = supported()
+                    .into_iter()
+                    .map(|simd| unsafe {
+                        let acc = Accumulator::refresh_with(simd, &network, &board)
+                            .update_with(simd, &network, &board, mv);
+                        (acc, acc.evaluate_with(simd, &network, Color::White))
+                    })
+                    .collect();
+                assert!(results.windows(2).all(|w| w[0] == w[1]), "{fen} {mv}");
+
+                board.play_unchecked(mv);
+            }
+        }
+    }
+
+    #[test]
+    fn crelu_dot_extremes() {
+        let values = std::array::from_fn(|i| [i16::MIN, -1, 0, 100, 255, 256, i16::MAX][i % 7]);
+        let weights = std::array::from_fn(|i| [i16::MIN, i16::MAX, -3, 7][i % 4]);
+
+        let expected = unsafe { crelu_dot_with(Simd::Scalar, &values, &weights) };
+        for simd in supported() {
+            assert_eq!(
+                unsafe { crelu_dot_with(simd, &values, &weights) },
+                expected,
+                "{simd:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,10 @@
+// Layout of every evaluation weight as a single flat list of (mg, eg) pairs.
+// The tuner uses this to map the features recorded by a trace back to the weights.
+
+pub const PIECE_VALUE: usize = 0;
+pub const PST: usize = PIECE_VALUE + 6;
+pub const DOUBLED_PAWN: usize = PST + 6 * 64;
+pub const ISOLATED_PAWN: usize = DOUBLED_PAWN + 1;
+pub const PASSED_PAWN: usize = ISOLATED_PAWN + 1;
+pub const BLOCKED_PASSER: usize = PASSED_PAWN + 8;
+pub const PAWN_THREAT: usize = BLOCKED_PASSER + 1;
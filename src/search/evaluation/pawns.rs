@@ -0,0 +1,147 @@
+use super::{
+    params,
+    psts::*,
+    trace::{Term, Trace},
+};
+use cozy_chess::{get_pawn_attacks, BitBoard, Board, Color, File, Piece, Square};
+
+// Zobrist keys for pawns only, so the pawn hash does not change when pieces move.
+const PAWN_KEYS: [[u64; 64]; 2] = {
+    let mut keys = [[0; 64]; 2];
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut color = 0;
+
+    while color < 2 {
+        let mut square = 0;
+        while square < 64 {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            keys[color][square] = state;
+            square += 1;
+        }
+        color += 1;
+    }
+    keys
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PawnEntry {
+    pub key: u64,
+    // Scores are from white's point of view.
+    pub mg: i32,
+    pub eg: i32,
+    // Indexed by color.
+    pub passed: [BitBoard; 2],
+    pub attacks: [BitBoard; 2],
+}
+
+pub fn pawn_key(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for color in Color::ALL {
+        for square in board.colored_pieces(color, Piece::Pawn) {
+            key ^= PAWN_KEYS[color as usize][square as usize];
+        }
+    }
+
+    key
+}
+
+// Every square in front of the pawn, from the point of view of its owner.
+fn forward_ranks(square: Square, color: Color) -> BitBoard {
+    let rank = square.rank() as u32;
+
+    match color {
+        Color::White => BitBoard(u64::MAX.checked_shl(8 * (rank + 1)).unwrap_or(0)),
+        Color::Black => BitBoard((1u64 << (8 * rank)) - 1),
+    }
+}
+
+pub fn is_passed(board: &Board, square: Square, color: Color) -> bool {
+    let file = square.file();
+    let span = forward_ranks(square, color) & (file.bitboard() | file.adjacent());
+
+    (span & board.colored_pieces(!color, Piece::Pawn)).is_empty()
+}
+
+// Evaluates the pawn structure directly, without going through the pawn hash table.
+pub fn evaluate_pawns<T: Trace>(board: &Board, trace: &mut T) -> PawnEntry {
+    let mut entry = PawnEntry {
+        key: pawn_key(board),
+        ..Default::default()
+    };
+
+    for color in Color::ALL {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let pawns = board.colored_pieces(color, Piece::Pawn);
+
+        for file in File::ALL {
+            let on_file = (pawns & file.bitboard()).len() as i32;
+            if on_file == 0 {
+                continue;
+            }
+
+            if on_file > 1 {
+                let (mg, eg) = (
+                    (on_file - 1) * MG_DOUBLED_PAWN,
+                    (on_file - 1) * EG_DOUBLED_PAWN,
+                );
+                entry.mg += sign * mg;
+                entry.eg += sign * eg;
+                trace.add(Term::DoubledPawns, color, mg, eg);
+                trace.coeff(params::DOUBLED_PAWN, color, on_file - 1);
+            }
+
+            if (pawns & file.adjacent()).is_empty() {
+                let (mg, eg) = (on_file * MG_ISOLATED_PAWN, on_file * EG_ISOLATED_PAWN);
+                entry.mg += sign * mg;
+                entry.eg += sign * eg;
+                trace.add(Term::IsolatedPawns, color, mg, eg);
+                trace.coeff(params::ISOLATED_PAWN, color, on_file);
+            }
+        }
+
+        for square in pawns {
+            entry.attacks[color as usize] |= get_pawn_attacks(square, color);
+
+            if is_passed(board, square, color) {
+                let rank = square.rank().relative_to(color) as usize;
+                entry.passed[color as usize] |= square.bitboard();
+                entry.mg += sign * MG_PASSED_PAWN[rank];
+                entry.eg += sign * EG_PASSED_PAWN[rank];
+                trace.add(
+                    Term::PassedPawns,
+                    color,
+                    MG_PASSED_PAWN[rank],
+                    EG_PASSED_PAWN[rank],
+                );
+                trace.coeff(params::PASSED_PAWN + rank, color, 1);
+            }
+        }
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::evaluation::trace::NoTrace;
+
+    #[test]
+    fn passed_pawns() {
+        let board: Board = "4k3/8/1p6/8/1P2P3/8/6P1/4K3 w - - 0 1".parse().unwrap();
+        let entry = evaluate_pawns(&board, &mut NoTrace);
+
+        assert_eq!(
+            entry.passed[Color::White as usize],
+            Square::E4.bitboard() | Square::G2.bitboard()
+        );
+        assert!(entry.passed[Color::Black as usize].is_empty());
+    }
+}
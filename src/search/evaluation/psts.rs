@@ -1,5 +1,20 @@
-const MG_PIECE_VALUES: [i32; 6] = [82, 337, 365, 477, 1025, 0];
-const EG_PIECE_VALUES: [i32; 6] = [94, 281, 297, 512, 936, 0];
+pub const MG_PIECE_VALUES: [i32; 6] = [82, 337, 365, 477, 1025, 0];
+pub const EG_PIECE_VALUES: [i32; 6] = [94, 281, 297, 512, 936, 0];
+
+pub const MG_DOUBLED_PAWN: i32 = -10;
+pub const EG_DOUBLED_PAWN: i32 = -20;
+pub const MG_ISOLATED_PAWN: i32 = -12;
+pub const EG_ISOLATED_PAWN: i32 = -8;
+
+// Indexed by the rank of the pawn, relative to its owner.
+pub const MG_PASSED_PAWN: [i32; 8] = [0, 0, 5, 10, 20, 35, 60, 0];
+pub const EG_PASSED_PAWN: [i32; 8] = [0, 10, 15, 25, 45, 75, 120, 0];
+pub const MG_BLOCKED_PASSER: i32 = -5;
+pub const EG_BLOCKED_PASSER: i32 = -15;
+
+// Per non-pawn piece attacked by an enemy pawn.
+pub const MG_PAWN_THREAT: i32 = 40;
+pub const EG_PAWN_THREAT: i32 = 30;
 
 #[rustfmt::skip]
 const MG_PAWN_PST: [i32; 64] = [
@@ -142,10 +157,10 @@ const EG_KING_PST: [i32; 64] = [
     -18,  -4,  21,  24,  27,  23,   9, -11,
     -19,  -3,  11,  21,  23,  16,   7,  -9,
     -27, -11,   4,  13,  14,   4,  -5, -17,
-    -53, -34, -21, -11, -28, -14, -24, -43
+    -53, -34, -21, -11, -28, -14, -24, -43,
 ];
 
-const MG_PST: [[i32; 64]; 6] = [
+pub const MG_PST: [[i32; 64]; 6] = [
     MG_PAWN_PST,
     MG_KNIGHT_PST,
     MG_BISHOP_PST,
@@ -154,7 +169,7 @@ const MG_PST: [[i32; 64]; 6] = [
     MG_KING_PST,
 ];
 
-const EG_PST: [[i32; 64]; 6] = [
+pub const EG_PST: [[i32; 64]; 6] = [
     EG_PAWN_PST,
     EG_KNIGHT_PST,
     EG_BISHOP_PST,
@@ -0,0 +1,158 @@
+use cozy_chess::Color;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Term {
+    Material,
+    Psts,
+    DoubledPawns,
+    IsolatedPawns,
+    PassedPawns,
+    BlockedPassers,
+    PawnThreats,
+}
+
+impl Term {
+    pub const ALL: [Term; 7] = [
+        Term::Material,
+        Term::Psts,
+        Term::DoubledPawns,
+        Term::IsolatedPawns,
+        Term::PassedPawns,
+        Term::BlockedPassers,
+        Term::PawnThreats,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::Psts => "PSTs",
+            Term::DoubledPawns => "Doubled pawns",
+            Term::IsolatedPawns => "Isolated pawns",
+            Term::PassedPawns => "Passed pawns",
+            Term::BlockedPassers => "Blocked passers",
+            Term::PawnThreats => "Pawn threats",
+        }
+    }
+
+    // The PeSTO tables are not symmetric between the queen and king side.
+    pub fn is_file_symmetric(self) -> bool {
+        self != Term::Psts
+    }
+}
+
+// Collects the contribution of each evaluation term. The evaluation is generic over
+// this so that `NoTrace` compiles every call away in the search.
+pub trait Trace {
+    // `mg` and `eg` are from the point of view of `color`.
+    fn add(&mut self, term: Term, color: Color, mg: i32, eg: i32);
+
+    fn set_phase(&mut self, _phase: i32) {}
+
+    // Records that the weight at `param` (see `params`) is used `count` times for `color`.
+    fn coeff(&mut self, _param: usize, _color: Color, _count: i32) {}
+}
+
+pub struct NoTrace;
+
+impl Trace for NoTrace {
+    #[inline(always)]
+    fn add(&mut self, _: Term, _: Color, _: i32, _: i32) {}
+}
+
+#[derive(Clone, Debug)]
+pub struct EvalTrace {
+    // Indexed by term, color, then mg/eg.
+    pub terms: [[[i32; 2]; 2]; Term::ALL.len()],
+    pub phase: i32,
+    // Tapered score, from white's point of view.
+    pub score: i32,
+    pub side_to_move: Color,
+}
+
+impl Trace for EvalTrace {
+    fn add(&mut self, term: Term, color: Color, mg: i32, eg: i32) {
+        let entry = &mut self.terms[term as usize][color as usize];
+        entry[0] += mg;
+        entry[1] += eg;
+    }
+
+    fn set_phase(&mut self, phase: i32) {
+        self.phase = phase;
+    }
+}
+
+impl EvalTrace {
+    pub fn new(side_to_move: Color) -> Self {
+        EvalTrace {
+            terms: [[[0; 2]; 2]; Term::ALL.len()],
+            phase: 0,
+            score: 0,
+            side_to_move,
+        }
+    }
+
+    pub fn term(&self, term: Term, color: Color) -> (i32, i32) {
+        let [mg, eg] = self.terms[term as usize][color as usize];
+        (mg, eg)
+    }
+
+    // White minus black.
+    pub fn total(&self, term: Term) -> (i32, i32) {
+        let (wmg, weg) = self.term(term, Color::White);
+        let (bmg, beg) = self.term(term, Color::Black);
+        (wmg - bmg, weg - beg)
+    }
+}
+
+fn pawns(cp: i32) -> String {
+    format!("{:6.2}", cp as f64 / 100.0)
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "      Term       |     White     |     Black     |     Total"
+        )?;
+        writeln!(
+            f,
+            "                 |   MG     EG   |   MG     EG   |   MG     EG"
+        )?;
+        writeln!(
+            f,
+            " ----------------+---------------+---------------+---------------"
+        )?;
+
+        for term in Term::ALL {
+            let (wmg, weg) = self.term(term, Color::White);
+            let (bmg, beg) = self.term(term, Color::Black);
+            let (tmg, teg) = self.total(term);
+            writeln!(
+                f,
+                " {:>15} | {} {} | {} {} | {} {}",
+                term.name(),
+                pawns(wmg),
+                pawns(weg),
+                pawns(bmg),
+                pawns(beg),
+                pawns(tmg),
+                pawns(teg)
+            )?;
+        }
+
+        let stm = match self.side_to_move {
+            Color::White => self.score,
+            Color::Black => -self.score,
+        };
+
+        writeln!(f)?;
+        writeln!(f, "Phase: {} / 24", self.phase)?;
+        writeln!(
+            f,
+            "Final evaluation: {} (white side)",
+            pawns(self.score).trim()
+        )?;
+        write!(f, "Final evaluation: {} (side to move)", pawns(stm).trim())
+    }
+}
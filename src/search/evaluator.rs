@@ -0,0 +1,49 @@
+#[cfg(not(feature = "nnue"))]
+use super::eval::eval;
+#[cfg(feature = "nnue")]
+use super::evaluation::nnue::Nnue;
+use super::pawn_table::{PawnTable, DEFAULT_PAWN_HASH_MB};
+use cozy_chess::{Board, Move};
+
+// The static evaluation used by the search: the PSTs by default, or NNUE with
+// the `nnue` feature. Moves have to be pushed and popped as the search goes.
+pub struct Evaluator {
+    pub pawn_table: PawnTable,
+    #[cfg(feature = "nnue")]
+    nnue: Nnue,
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Evaluator {
+            pawn_table: PawnTable::new(DEFAULT_PAWN_HASH_MB),
+            #[cfg(feature = "nnue")]
+            nnue: Nnue::new(),
+        }
+    }
+
+    pub fn reset(&mut self, _board: &Board) {
+        #[cfg(feature = "nnue")]
+        self.nnue.reset(_board);
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, _board: &Board, _mv: Move) {
+        #[cfg(feature = "nnue")]
+        self.nnue.push(_board, _mv);
+    }
+
+    #[inline(always)]
+    pub fn pop(&mut self) {
+        #[cfg(feature = "nnue")]
+        self.nnue.pop();
+    }
+
+    pub fn evaluate(&mut self, board: &Board) -> i16 {
+        #[cfg(feature = "nnue")]
+        return self.nnue.evaluate(board);
+
+        #[cfg(not(feature = "nnue"))]
+        eval(board, &mut self.pawn_table)
+    }
+}
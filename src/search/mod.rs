@@ -1,7 +1,10 @@
-mod definitions;
-mod eval;
+pub mod definitions;
+pub mod eval;
+pub mod evaluation;
+pub mod evaluator;
 pub mod options;
+pub mod pawn_table;
 mod pv_table;
+#[allow(clippy::module_inception)]
 pub mod search;
 mod timeman;
-mod evaluation;
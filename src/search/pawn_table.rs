@@ -0,0 +1,87 @@
+use super::evaluation::{
+    pawns::{evaluate_pawns, pawn_key, PawnEntry},
+    trace::NoTrace,
+};
+use cozy_chess::Board;
+
+pub const DEFAULT_PAWN_HASH_MB: usize = 2;
+
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl PawnTable {
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / std::mem::size_of::<PawnEntry>()).max(1);
+
+        PawnTable {
+            entries: vec![PawnEntry::default(); len],
+        }
+    }
+
+    pub fn resize(&mut self, mb: usize) {
+        *self = PawnTable::new(mb);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(PawnEntry::default());
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    // Returns the cached pawn structure, evaluating and storing it on a miss.
+    pub fn probe(&mut self, board: &Board) -> PawnEntry {
+        let key = pawn_key(board);
+        let index = self.index(key);
+
+        // A key of 0 (no pawns on the board) is cheap to evaluate, so an empty slot
+        // being mistaken for it is harmless.
+        if self.entries[index].key == key && key != 0 {
+            return self.entries[index];
+        }
+
+        let entry = evaluate_pawns(board, &mut NoTrace);
+        self.entries[index] = entry;
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uci::bench::FENS;
+
+    #[test]
+    fn hits_and_misses() {
+        // The second table has a single slot, so every new pawn structure is a collision.
+        for mut table in [
+            PawnTable::new(1),
+            PawnTable {
+                entries: vec![PawnEntry::default(); 1],
+            },
+        ] {
+            for fen in FENS {
+                let board = Board::from_fen(fen, false).unwrap();
+                let expected = evaluate_pawns(&board, &mut NoTrace);
+
+                // Miss
+                assert_eq!(table.probe(&board), expected);
+                assert_eq!(table.entries[table.index(expected.key)], expected);
+                // Hit
+                assert_eq!(table.probe(&board), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let mut table = PawnTable::new(1);
+        let board = Board::startpos();
+        let key = table.probe(&board).key;
+
+        table.clear();
+        assert_eq!(table.entries[table.index(key)], PawnEntry::default());
+    }
+}
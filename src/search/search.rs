@@ -1,15 +1,38 @@
 use super::{
-    definitions::{INFINITY, MATE, MAX_PLY},
-    eval::eval,
+    definitions::{moves_to_mate, INFINITY, MATE, MAX_PLY},
+    evaluator::Evaluator,
     options::SearchOptions,
     pv_table::PVTable,
 };
-use crate::{chess::move_gen, search::timeman::timeman};
+use crate::{
+    chess::{move_gen, parse_move::move_to_string},
+    search::timeman::timeman,
+};
 use cozy_chess::{Board, GameStatus, Move};
 use std::time::Instant;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Output {
+    Uci,
+    // Thinking output, printed by xboard when `post` is on
+    Xboard,
+    Silent,
+}
+
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: i16,
+    pub depth: u8,
+    pub pv: Vec<Move>,
+    // The best move after each completed iteration.
+    pub best_moves: Vec<Move>,
+}
+
 pub struct SearchInfo {
     pub nodes: u64,
+    pub evaluator: Evaluator,
+    pub output: Output,
+    node_limit: Option<u64>,
     start_timer: Option<Instant>,
     stop_time: Option<u32>,
     stop_flag: bool,
@@ -19,6 +42,9 @@ impl SearchInfo {
     pub fn new() -> Self {
         SearchInfo {
             nodes: 0,
+            evaluator: Evaluator::new(),
+            output: Output::Uci,
+            node_limit: None,
             start_timer: None,
             stop_time: None,
             stop_flag: false,
@@ -27,6 +53,7 @@ impl SearchInfo {
 
     pub fn reset(&mut self) {
         self.nodes = 0;
+        self.node_limit = None;
         self.start_timer = None;
         self.stop_time = None;
         self.stop_flag = false;
@@ -43,7 +70,7 @@ pub fn search(
     pv: &mut PVTable,
 ) -> i16 {
     // Every 1024 nodes, check if we should stop
-    if info.nodes % 1024 == 0 {
+    if info.nodes.is_multiple_of(1024) {
         if let (Some(timer), Some(stop_time)) = (info.start_timer, info.stop_time) {
             if timer.elapsed().as_millis() as u32 >= stop_time {
                 info.stop_flag = true;
@@ -51,13 +78,19 @@ pub fn search(
         }
     }
 
+    if let Some(limit) = info.node_limit {
+        if info.nodes >= limit {
+            info.stop_flag = true;
+        }
+    }
+
     // Always let depth 1 finish!
     if info.stop_flag && ply > 0 {
         return 0;
     }
 
     if ply >= MAX_PLY {
-        return eval(board);
+        return info.evaluator.evaluate(board);
     }
 
     // The PVTable that will get passed down the search tree
@@ -66,7 +99,7 @@ pub fn search(
     pv.length = 0;
 
     if depth == 0 {
-        return eval(board);
+        return info.evaluator.evaluate(board);
     }
 
     match board.status() {
@@ -81,6 +114,7 @@ pub fn search(
     for mv in moves {
         let mut new_board = board.clone();
         new_board.play_unchecked(mv);
+        info.evaluator.push(board, mv);
         info.nodes += 1;
 
         let score = -search(
@@ -92,6 +126,7 @@ pub fn search(
             ply + 1,
             &mut old_pv,
         );
+        info.evaluator.pop();
 
         if score <= best_score {
             continue;
@@ -114,9 +149,15 @@ pub fn search(
     best_score
 }
 
-pub fn search_root(info: &mut SearchInfo, board: &Board, option: SearchOptions, frc: bool) {
+pub fn search_root(
+    info: &mut SearchInfo,
+    board: &Board,
+    option: SearchOptions,
+    frc: bool,
+) -> Option<SearchResult> {
     let mut pv = PVTable::new();
-    let mut best_move: Option<Move> = None;
+    let mut result: Option<SearchResult> = None;
+    let mut best_moves = Vec::new();
     let info_timer = Instant::now();
 
     let depth = match option {
@@ -131,9 +172,15 @@ pub fn search_root(info: &mut SearchInfo, board: &Board, option: SearchOptions,
             info.stop_time = Some(t);
             MAX_PLY
         }
-        SearchOptions::Infinite | SearchOptions::Nodes(_) => MAX_PLY,
+        SearchOptions::Nodes(n) => {
+            info.node_limit = Some(n);
+            MAX_PLY
+        }
+        SearchOptions::Infinite => MAX_PLY,
     };
 
+    info.evaluator.reset(board);
+
     for d in 1..=depth {
         let score = search(info, -INFINITY, INFINITY, board, d, 0, &mut pv);
 
@@ -143,27 +190,69 @@ pub fn search_root(info: &mut SearchInfo, board: &Board, option: SearchOptions,
             break;
         }
 
+        // Mated or stalemated, there is nothing to report.
+        let Some(best_move) = pv.table[0] else {
+            break;
+        };
+
         let elapsed = info_timer.elapsed().as_millis() as u64;
-        println!(
-            "info depth {} score cp {} nodes {} nps {} time {} pv {}",
-            d,
+        match info.output {
+            Output::Uci => println!(
+                "info depth {} score cp {} nodes {} nps {} time {} pv {}",
+                d,
+                score,
+                info.nodes,
+                info.nodes / (elapsed / 1000).max(1),
+                elapsed,
+                pv.to_string(board, frc)
+            ),
+            // ply, score, time in centiseconds, nodes, pv
+            Output::Xboard => println!(
+                "{} {} {} {} {}",
+                d,
+                xboard_score(score),
+                elapsed / 10,
+                info.nodes,
+                pv.to_string(board, frc)
+            ),
+            Output::Silent => {}
+        }
+
+        best_moves.push(best_move);
+        result = Some(SearchResult {
+            best_move,
             score,
-            info.nodes,
-            info.nodes / (elapsed / 1000).max(1),
-            elapsed,
-            pv.to_string(board, frc)
-        );
+            depth: d,
+            pv: pv.moves().iter().flatten().copied().collect(),
+            best_moves: Vec::new(),
+        });
 
-        best_move = pv.table[0];
+        if info.stop_flag {
+            break;
+        }
+    }
 
-        if let SearchOptions::Nodes(n) = option {
-            if info.nodes >= n {
-                break;
-            }
+    // UCI has no move for positions without legal moves, GUIs expect the null move.
+    let Some(mut result) = result else {
+        if info.output == Output::Uci {
+            println!("bestmove 0000");
         }
+        return None;
+    };
+    result.best_moves = best_moves;
+    if info.output == Output::Uci {
+        println!("bestmove {}", bestmove(board, result.best_move, frc));
     }
 
-    println!("bestmove {}", best_move.unwrap());
+    Some(result)
+}
+
+// Xboard reports mate in N moves as 100000 + N.
+fn xboard_score(score: i16) -> i32 {
+    match moves_to_mate(score) {
+        Some(moves) => score.signum() as i32 * 100_000 + moves,
+        None => score as i32,
+    }
 }
 
 fn draw_score() -> i16 {
@@ -174,6 +263,16 @@ fn mated_in(ply: u8) -> i16 {
     -MATE + ply as i16
 }
 
+// Castling is reported as king-to-target like the PV, unless playing FRC.
+fn bestmove(board: &Board, mv: Move, frc: bool) -> String {
+    if frc {
+        mv.to_string()
+    } else {
+        move_to_string(board, mv)
+    }
+}
+
+#[cfg(test)]
 fn mate_in(ply: u8) -> i16 {
     MATE - ply as i16
 }
@@ -202,16 +301,10 @@ mod tests {
         for (fen, mv) in TESTS.iter() {
             let mut info = SearchInfo::new();
             let mut pv = PVTable::new();
+            let board = fen.parse().unwrap();
+            info.evaluator.reset(&board);
 
-            let score = search(
-                &mut info,
-                -INFINITY,
-                INFINITY,
-                &fen.parse().unwrap(),
-                3,
-                0,
-                &mut pv,
-            );
+            let score = search(&mut info, -INFINITY, INFINITY, &board, 3, 0, &mut pv);
 
             assert_eq!(score, mate_in(1));
             assert_eq!(pv.table[0], Some(mv.parse().unwrap()));
@@ -228,16 +321,10 @@ mod tests {
         for (fen, mv) in TESTS.iter() {
             let mut info = SearchInfo::new();
             let mut pv = PVTable::new();
+            let board = fen.parse().unwrap();
+            info.evaluator.reset(&board);
 
-            let score = search(
-                &mut info,
-                -INFINITY,
-                INFINITY,
-                &fen.parse().unwrap(),
-                3,
-                0,
-                &mut pv,
-            );
+            let score = search(&mut info, -INFINITY, INFINITY, &board, 3, 0, &mut pv);
 
             assert_eq!(score, mated_in(2));
             assert_eq!(pv.table[0], Some(mv.parse().unwrap()));
@@ -264,16 +351,10 @@ mod tests {
         for (fen, mv) in TESTS.iter() {
             let mut info = SearchInfo::new();
             let mut pv = PVTable::new();
+            let board = fen.parse().unwrap();
+            info.evaluator.reset(&board);
 
-            let score = search(
-                &mut info,
-                -INFINITY,
-                INFINITY,
-                &fen.parse().unwrap(),
-                5,
-                0,
-                &mut pv,
-            );
+            let score = search(&mut info, -INFINITY, INFINITY, &board, 5, 0, &mut pv);
 
             assert_eq!(score, mate_in(3));
             assert_eq!(pv.table[0], Some(mv.parse().unwrap()));
@@ -296,16 +377,10 @@ mod tests {
         for (fen, mv) in TESTS.iter() {
             let mut info = SearchInfo::new();
             let mut pv = PVTable::new();
+            let board = fen.parse().unwrap();
+            info.evaluator.reset(&board);
 
-            let score = search(
-                &mut info,
-                -INFINITY,
-                INFINITY,
-                &fen.parse().unwrap(),
-                5,
-                0,
-                &mut pv,
-            );
+            let score = search(&mut info, -INFINITY, INFINITY, &board, 5, 0, &mut pv);
 
             assert_eq!(score, mated_in(4));
             assert_eq!(pv.table[0], Some(mv.parse().unwrap()));
@@ -322,18 +397,22 @@ mod tests {
         for fen in TESTS.iter() {
             let mut info = SearchInfo::new();
             let mut pv = PVTable::new();
+            let board = fen.parse().unwrap();
+            info.evaluator.reset(&board);
 
-            let score = search(
-                &mut info,
-                -INFINITY,
-                INFINITY,
-                &fen.parse().unwrap(),
-                5,
-                0,
-                &mut pv,
-            );
+            let score = search(&mut info, -INFINITY, INFINITY, &board, 5, 0, &mut pv);
 
             assert_eq!(score, 0);
         }
     }
+
+    #[test]
+    fn castling_bestmove() {
+        let board: Board = "4k3/8/8/8/8/8/8/4K2R w K - 0 1".parse().unwrap();
+        let castle = "e1h1".parse().unwrap();
+
+        assert_eq!(bestmove(&board, castle, false), "e1g1");
+        assert_eq!(bestmove(&board, castle, true), "e1h1");
+        assert_eq!(bestmove(&board, "h1h8".parse().unwrap(), false), "h1h8");
+    }
 }
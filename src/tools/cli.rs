@@ -0,0 +1,385 @@
+use crate::{
+    chess::{parse_move::move_to_string, perft::PerftOptions},
+    search::{
+        definitions::moves_to_mate,
+        eval::eval_trace,
+        evaluation::trace::Term,
+        options::SearchOptions,
+        search::{search_root, Output, SearchInfo},
+    },
+};
+use cozy_chess::{Board, Color};
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Search,
+    Perft,
+    Eval,
+}
+
+struct CliOptions {
+    board: Board,
+    // Shredder castling rights, printed with king-takes-rook castling moves
+    frc: bool,
+    json: bool,
+    limit: SearchOptions,
+    depth: Option<u8>,
+    divide: bool,
+    perft: PerftOptions,
+}
+
+// Each subcommand only accepts --fen, --json and its own arguments.
+fn parse_args(command: Command, args: &[String]) -> Option<CliOptions> {
+    let mut options = CliOptions {
+        board: Board::startpos(),
+        frc: false,
+        json: false,
+        limit: SearchOptions::Movetime(1000),
+        depth: None,
+        divide: false,
+        perft: PerftOptions::new(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fen" => {
+                let fen = args.next()?;
+                (options.board, options.frc) = match Board::from_fen(fen, false) {
+                    Ok(board) => (board, false),
+                    Err(_) => (Board::from_fen(fen, true).ok()?, true),
+                };
+            }
+            "--json" => options.json = true,
+            "--depth" if command == Command::Search => {
+                options.limit = SearchOptions::Depth(args.next()?.parse().ok()?)
+            }
+            "--nodes" if command == Command::Search => {
+                options.limit = SearchOptions::Nodes(args.next()?.parse().ok()?)
+            }
+            "--movetime" if command == Command::Search => {
+                options.limit = SearchOptions::Movetime(args.next()?.parse().ok()?)
+            }
+            "--divide" if command == Command::Perft => options.divide = true,
+            "--threads" if command == Command::Perft => {
+                options.perft.threads = args.next()?.parse::<usize>().ok()?.max(1)
+            }
+            "--hash" if command == Command::Perft => {
+                options.perft.set_hash(args.next()?.parse().ok()?)
+            }
+            depth if command == Command::Perft => options.depth = Some(depth.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    Some(options)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn fen(options: &CliOptions) -> String {
+    if options.frc {
+        format!("{:#}", options.board)
+    } else {
+        options.board.to_string()
+    }
+}
+
+fn run_search(options: &CliOptions) -> Result<String, String> {
+    let board = &options.board;
+    if !board.generate_moves(|_| true) {
+        return Err(String::from("no legal moves"));
+    }
+
+    let mut info = SearchInfo::new();
+    info.output = Output::Silent;
+    let start = Instant::now();
+    let result =
+        search_root(&mut info, board, options.limit, options.frc).ok_or("no search result")?;
+    let time = start.elapsed().as_millis();
+
+    let mut pv = Vec::new();
+    let mut pv_board = board.clone();
+    for &mv in &result.pv {
+        pv.push(if options.frc {
+            mv.to_string()
+        } else {
+            move_to_string(&pv_board, mv)
+        });
+        pv_board.play_unchecked(mv);
+    }
+    let best_move = pv.first().cloned().unwrap_or_default();
+
+    if options.json {
+        let score = match moves_to_mate(result.score) {
+            Some(mate) => format!("{{\"mate\":{mate}}}"),
+            None => format!("{{\"cp\":{}}}", result.score),
+        };
+        let pv: Vec<String> = pv.iter().map(|mv| json_string(mv)).collect();
+        return Ok(format!(
+            "{{\"fen\":{},\"depth\":{},\"score\":{},\"nodes\":{},\"time_ms\":{},\"bestmove\":{},\"pv\":[{}]}}",
+            json_string(&fen(options)),
+            result.depth,
+            score,
+            info.nodes,
+            time,
+            json_string(&best_move),
+            pv.join(",")
+        ));
+    }
+
+    let score = match moves_to_mate(result.score) {
+        Some(mate) => format!("mate {mate}"),
+        None => format!("cp {}", result.score),
+    };
+    Ok(format!(
+        "depth {}\nscore {score}\nnodes {}\ntime {time} ms\nbestmove {best_move}\npv {}",
+        result.depth,
+        info.nodes,
+        pv.join(" ")
+    ))
+}
+
+fn run_perft(options: &CliOptions) -> Result<String, String> {
+    let depth = options.depth.ok_or("missing depth")?;
+    let board = &options.board;
+
+    let start = Instant::now();
+    let divide: Vec<(String, u64)> = options
+        .perft
+        .divide(board, depth)
+        .into_iter()
+        .map(|(mv, nodes)| {
+            let name = if options.frc {
+                mv.to_string()
+            } else {
+                move_to_string(board, mv)
+            };
+            (name, nodes)
+        })
+        .collect();
+    let time = start.elapsed();
+
+    // Nothing is divided at depth 0, but the position itself is one node.
+    let nodes = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, nodes)| nodes).sum()
+    };
+    let nps = (nodes as f64 / time.as_secs_f64().max(1e-9)) as u64;
+
+    if options.json {
+        let mut json = format!(
+            "{{\"fen\":{},\"depth\":{depth},\"nodes\":{nodes},\"time_ms\":{},\"nps\":{nps}",
+            json_string(&fen(options)),
+            time.as_millis()
+        );
+        if options.divide {
+            let moves: Vec<String> = divide
+                .iter()
+                .map(|(mv, nodes)| format!("{}:{nodes}", json_string(mv)))
+                .collect();
+            json += &format!(",\"divide\":{{{}}}", moves.join(","));
+        }
+        json.push('}');
+        return Ok(json);
+    }
+
+    let mut out = String::new();
+    if options.divide {
+        for (mv, nodes) in &divide {
+            out += &format!("{mv} {nodes}\n");
+        }
+    }
+    out += &format!("nodes {nodes}\ntime {} ms\nnps {nps}", time.as_millis());
+    Ok(out)
+}
+
+fn run_eval(options: &CliOptions) -> Result<String, String> {
+    let trace = eval_trace(&options.board);
+    #[cfg(feature = "nnue")]
+    let nnue = {
+        use crate::search::evaluation::nnue::{accumulator::Accumulator, network};
+
+        let network = network();
+        Accumulator::refresh(&network, &options.board)
+            .evaluate(&network, options.board.side_to_move())
+    };
+
+    if !options.json {
+        #[cfg(feature = "nnue")]
+        return Ok(format!(
+            "{trace}\nNNUE evaluation: {:.2} (side to move)",
+            nnue as f64 / 100.0
+        ));
+        #[cfg(not(feature = "nnue"))]
+        return Ok(trace.to_string());
+    }
+
+    let terms: Vec<String> = Term::ALL
+        .iter()
+        .map(|&term| {
+            let (wmg, weg) = trace.term(term, Color::White);
+            let (bmg, beg) = trace.term(term, Color::Black);
+            format!(
+                "{}:{{\"white\":[{wmg},{weg}],\"black\":[{bmg},{beg}]}}",
+                json_string(term.name())
+            )
+        })
+        .collect();
+    let stm = match trace.side_to_move {
+        Color::White => trace.score,
+        Color::Black => -trace.score,
+    };
+
+    #[cfg(feature = "nnue")]
+    let nnue = format!(",\"nnue\":{nnue}");
+    #[cfg(not(feature = "nnue"))]
+    let nnue = String::new();
+
+    Ok(format!(
+        "{{\"fen\":{},\"phase\":{},\"white\":{},\"score\":{stm},\"terms\":{{{}}}{nnue}}}",
+        json_string(&fen(options)),
+        trace.phase,
+        trace.score,
+        terms.join(",")
+    ))
+}
+
+fn report(result: Result<String, String>, json: bool) -> bool {
+    match result {
+        Ok(out) => {
+            println!("{out}");
+            true
+        }
+        Err(e) if json => {
+            println!("{{\"error\":{}}}", json_string(&e));
+            false
+        }
+        Err(e) => {
+            println!("Error: {e}");
+            false
+        }
+    }
+}
+
+// `honse search`, `honse perft` and `honse eval` return false on failure.
+pub fn search(args: &[String]) -> bool {
+    match parse_args(Command::Search, args) {
+        Some(options) => report(run_search(&options), options.json),
+        None => {
+            println!("Usage: honse search [--fen <fen>] [--depth <n> | --nodes <n> | --movetime <ms>] [--json]");
+            false
+        }
+    }
+}
+
+pub fn perft(args: &[String]) -> bool {
+    match parse_args(Command::Perft, args) {
+        Some(options) => report(run_perft(&options), options.json),
+        None => {
+            println!("Usage: honse perft [--fen <fen>] <depth> [--divide] [--threads <n>] [--hash <mb>] [--json]");
+            false
+        }
+    }
+}
+
+pub fn eval(args: &[String]) -> bool {
+    match parse_args(Command::Eval, args) {
+        Some(options) => report(run_eval(&options), options.json),
+        None => {
+            println!("Usage: honse eval [--fen <fen>] [--json]");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::definitions::MATE;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split(' ').map(String::from).collect()
+    }
+
+    fn options(command: Command, args: &str) -> CliOptions {
+        parse_args(command, &self::args(args)).unwrap()
+    }
+
+    #[test]
+    fn search() {
+        // Mate in one with Ra8
+        let mut options = options(Command::Search, "--depth 3");
+        options.board = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".parse().unwrap();
+
+        let out = run_search(&options).unwrap();
+        assert!(out.contains("score mate 1\n"), "{out}");
+        assert!(out.contains("bestmove a1a8\n"), "{out}");
+
+        options.json = true;
+        let out = run_search(&options).unwrap();
+        assert!(out.starts_with("{\"fen\":\"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\",\"depth\":3,\"score\":{\"mate\":1},"), "{out}");
+        assert!(
+            out.ends_with("\"bestmove\":\"a1a8\",\"pv\":[\"a1a8\"]}"),
+            "{out}"
+        );
+
+        options.board = "R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1".parse().unwrap();
+        assert!(run_search(&options).is_err());
+        assert!(parse_args(Command::Search, &args("3")).is_none());
+        assert!(parse_args(Command::Search, &args("--depth 3 --divide")).is_none());
+    }
+
+    #[test]
+    fn perft() {
+        let out = run_perft(&options(Command::Perft, "0 --divide")).unwrap();
+        assert!(out.starts_with("nodes 1\n"), "{out}");
+
+        let mut options = options(Command::Perft, "3 --divide");
+        let out = run_perft(&options).unwrap();
+        assert!(out.starts_with("a2a3 380\n"), "{out}");
+        assert!(out.contains("nodes 8902\n"), "{out}");
+
+        options.json = true;
+        let out = run_perft(&options).unwrap();
+        assert!(out.contains("\"depth\":3,\"nodes\":8902,"), "{out}");
+        assert!(out.contains("\"divide\":{\"a2a3\":380,"), "{out}");
+
+        options.depth = None;
+        assert!(run_perft(&options).is_err());
+        assert!(parse_args(Command::Perft, &args("x")).is_none());
+        assert!(parse_args(Command::Perft, &args("3 --depth 3")).is_none());
+    }
+
+    #[test]
+    fn eval() {
+        let out = run_eval(&options(Command::Eval, "--json")).unwrap();
+        assert!(out.starts_with("{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"phase\":24,\"white\":0,\"score\":0,\"terms\":{\"Material\":{\"white\":["), "{out}");
+        assert!(
+            out.contains("\"Doubled pawns\":{\"white\":[0,0],\"black\":[0,0]}"),
+            "{out}"
+        );
+        assert!(parse_args(Command::Eval, &args("--hash 16")).is_none());
+        assert!(parse_args(Command::Eval, &args("--nodes 100")).is_none());
+
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\n\"");
+        assert_eq!(moves_to_mate(MATE - 1), Some(1));
+        assert_eq!(moves_to_mate(-MATE + 4), Some(-2));
+        assert_eq!(moves_to_mate(300), None);
+    }
+}
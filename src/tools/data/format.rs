@@ -0,0 +1,128 @@
+use super::text;
+use crate::chess::packed::{PackedBoard, PackedReader};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Lines, Write},
+    path::Path,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    // `fen | score | result` lines
+    Text,
+    // Packed boards
+    Binary,
+}
+
+impl Format {
+    pub fn of(path: &str) -> Format {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("txt" | "epd") => Format::Text,
+            _ => Format::Binary,
+        }
+    }
+}
+
+enum Source {
+    Text(Lines<BufReader<File>>),
+    Binary(PackedReader<BufReader<File>>),
+}
+
+// Reads positions from either format. Text lines that fail to parse are skipped
+// and counted.
+pub struct Reader {
+    source: Source,
+    pub skipped: usize,
+}
+
+impl Reader {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let source = match Format::of(path) {
+            Format::Text => Source::Text(file.lines()),
+            Format::Binary => Source::Binary(PackedReader::new(file)),
+        };
+
+        Ok(Reader { source, skipped: 0 })
+    }
+}
+
+impl Iterator for Reader {
+    type Item = io::Result<PackedBoard>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.source {
+            Source::Binary(reader) => reader.next(),
+            Source::Text(lines) => loop {
+                let line = match lines.next()? {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                match text::to_packed(&line) {
+                    Some(packed) => return Some(Ok(packed)),
+                    None => self.skipped += 1,
+                }
+            },
+        }
+    }
+}
+
+// The number of positions in a file, without parsing them.
+pub fn count(path: &str) -> io::Result<usize> {
+    match Format::of(path) {
+        Format::Binary => Ok(fs::metadata(path)?.len() as usize / PackedBoard::SIZE),
+        Format::Text => {
+            let lines = BufReader::new(File::open(path)?).lines();
+            let mut count = 0;
+            for line in lines {
+                if !line?.trim().is_empty() {
+                    count += 1;
+                }
+            }
+            Ok(count)
+        }
+    }
+}
+
+pub struct Writer {
+    writer: BufWriter<File>,
+    format: Format,
+    pub written: usize,
+    // Packed boards that could not be turned back into a FEN.
+    pub skipped: usize,
+}
+
+impl Writer {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(Writer {
+            writer: BufWriter::new(File::create(path)?),
+            format: Format::of(path),
+            written: 0,
+            skipped: 0,
+        })
+    }
+
+    pub fn write(&mut self, packed: PackedBoard) -> io::Result<()> {
+        match self.format {
+            Format::Binary => packed.write(&mut self.writer)?,
+            Format::Text => match text::from_packed(packed) {
+                Some(line) => writeln!(self.writer, "{line}")?,
+                None => {
+                    self.skipped += 1;
+                    return Ok(());
+                }
+            },
+        }
+
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,356 @@
+mod format;
+pub mod text;
+
+use super::rng::Rng;
+use crate::{chess::packed::PackedBoard, search::eval::game_phase};
+use format::{Reader, Writer};
+use std::{collections::HashSet, io};
+
+const USAGE: &str = "Usage: honse data <command>
+  convert <input> <output>
+  stats <file>...
+  shuffle <input> <output> [--seed <n>]
+  dedupe <input> <output>
+  interleave <output> <input>... [--seed <n>]
+Files ending in .txt or .epd hold `fen | score | result` lines, anything else packed boards.";
+
+fn read_all(path: &str) -> io::Result<Vec<PackedBoard>> {
+    let mut reader = Reader::open(path)?;
+    let positions = reader.by_ref().collect::<io::Result<Vec<_>>>()?;
+    report_skipped(path, reader.skipped);
+    Ok(positions)
+}
+
+fn write_all(path: &str, positions: impl IntoIterator<Item = PackedBoard>) -> io::Result<()> {
+    let mut writer = Writer::create(path)?;
+    for packed in positions {
+        writer.write(packed)?;
+    }
+
+    println!("Wrote {} positions to {path}", writer.written);
+    report_skipped(path, writer.skipped);
+    writer.finish()
+}
+
+fn report_skipped(path: &str, skipped: usize) {
+    if skipped > 0 {
+        println!("Skipped {skipped} invalid positions in {path}");
+    }
+}
+
+// Streams, so files larger than memory can be converted.
+fn convert(input: &str, output: &str) -> io::Result<()> {
+    let mut reader = Reader::open(input)?;
+    let mut writer = Writer::create(output)?;
+    for packed in reader.by_ref() {
+        writer.write(packed?)?;
+    }
+
+    report_skipped(input, reader.skipped);
+    println!("Wrote {} positions to {output}", writer.written);
+    report_skipped(output, writer.skipped);
+    writer.finish()
+}
+
+#[derive(Default, PartialEq, Eq, Debug)]
+struct Stats {
+    count: usize,
+    invalid: usize,
+    // Indexed by result: black win, draw, white win
+    wdl: [usize; 3],
+    phases: [usize; 25],
+}
+
+fn collect_stats(paths: &[String]) -> io::Result<Stats> {
+    let mut stats = Stats::default();
+
+    for path in paths {
+        let mut reader = Reader::open(path)?;
+        for packed in reader.by_ref() {
+            let packed = packed?;
+            let Some(board) = packed.unpack().filter(|_| packed.wdl <= 2) else {
+                stats.invalid += 1;
+                continue;
+            };
+
+            stats.count += 1;
+            stats.wdl[packed.wdl as usize] += 1;
+            stats.phases[game_phase(&board).min(24) as usize] += 1;
+        }
+        stats.invalid += reader.skipped;
+    }
+
+    Ok(stats)
+}
+
+fn stats(paths: &[String]) -> io::Result<()> {
+    let Stats {
+        count,
+        invalid,
+        wdl,
+        phases,
+    } = collect_stats(paths)?;
+
+    let percent = |n: usize| 100.0 * n as f64 / count.max(1) as f64;
+    println!("Positions: {count}");
+    if invalid > 0 {
+        println!("Invalid: {invalid}");
+    }
+    println!("White wins: {} ({:.1}%)", wdl[2], percent(wdl[2]));
+    println!("Draws: {} ({:.1}%)", wdl[1], percent(wdl[1]));
+    println!("Black wins: {} ({:.1}%)", wdl[0], percent(wdl[0]));
+
+    println!("\nPhase (24 = opening, 0 = pawn ending)");
+    let largest = phases.iter().copied().max().unwrap_or(0).max(1);
+    for (phase, &n) in phases.iter().enumerate().rev() {
+        let bar = "#".repeat(n * 50 / largest);
+        println!("{phase:>5} | {n:>10} {:>5.1}% {bar}", percent(n));
+    }
+
+    Ok(())
+}
+
+fn shuffle(input: &str, output: &str, rng: &mut Rng) -> io::Result<()> {
+    let mut positions = read_all(input)?;
+
+    // Fisher-Yates
+    for i in (1..positions.len()).rev() {
+        positions.swap(i, rng.below(i + 1));
+    }
+
+    write_all(output, positions)
+}
+
+// Keeps the first occurrence of every position, by Zobrist key. Returns the
+// kept positions and the number of duplicates and invalid records dropped.
+fn unique(positions: Vec<PackedBoard>) -> (Vec<PackedBoard>, usize, usize) {
+    let mut seen = HashSet::new();
+    let (mut duplicates, mut invalid) = (0, 0);
+
+    let unique = positions
+        .into_iter()
+        .filter(|packed| match packed.unpack() {
+            Some(board) if seen.insert(board.hash()) => true,
+            Some(_) => {
+                duplicates += 1;
+                false
+            }
+            None => {
+                invalid += 1;
+                false
+            }
+        })
+        .collect();
+
+    (unique, duplicates, invalid)
+}
+
+fn dedupe(input: &str, output: &str) -> io::Result<()> {
+    let (unique, duplicates, invalid) = unique(read_all(input)?);
+
+    println!("Removed {duplicates} duplicates");
+    report_skipped(input, invalid);
+    write_all(output, unique)
+}
+
+// Mixes the inputs into one file, picking each position from a file with a
+// probability proportional to how many it has left.
+fn interleave(output: &str, inputs: &[String], rng: &mut Rng) -> io::Result<()> {
+    let mut readers = Vec::new();
+    let mut remaining = Vec::new();
+    for path in inputs {
+        readers.push(Reader::open(path)?);
+        remaining.push(format::count(path)?);
+    }
+
+    let mut writer = Writer::create(output)?;
+    let mut total: usize = remaining.iter().sum();
+
+    while total > 0 {
+        let mut pick = rng.below(total);
+        let index = remaining
+            .iter()
+            .position(|&n| {
+                if pick < n {
+                    return true;
+                }
+                pick -= n;
+                false
+            })
+            .unwrap();
+
+        match readers[index].next() {
+            Some(packed) => {
+                writer.write(packed?)?;
+                remaining[index] -= 1;
+                total -= 1;
+            }
+            // Skipped lines mean a text file can run out early.
+            None => {
+                total -= remaining[index];
+                remaining[index] = 0;
+            }
+        }
+    }
+
+    for (path, reader) in inputs.iter().zip(&readers) {
+        report_skipped(path, reader.skipped);
+    }
+    println!("Wrote {} positions to {output}", writer.written);
+    report_skipped(output, writer.skipped);
+    writer.finish()
+}
+
+// Splits off `--seed <n>`, seeding from the clock otherwise.
+fn take_seed(args: &[String]) -> Option<(Vec<String>, Rng)> {
+    let mut rest = Vec::new();
+    let mut seed = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = Some(args.next()?.parse().ok()?),
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    Some((rest, seed.map_or_else(Rng::from_time, Rng::new)))
+}
+
+pub fn data(args: &[String]) {
+    let Some((args, mut rng)) = take_seed(args) else {
+        println!("{USAGE}");
+        return;
+    };
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["convert", input, output] => convert(input, output),
+        ["stats", ..] if args.len() > 1 => stats(&args[1..]),
+        ["shuffle", input, output] => shuffle(input, output, &mut rng),
+        ["dedupe", input, output] => dedupe(input, output),
+        ["interleave", output, ..] if args.len() > 2 => interleave(output, &args[2..], &mut rng),
+        _ => {
+            println!("{USAGE}");
+            return;
+        }
+    };
+
+    if let Err(e) = result {
+        println!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cozy_chess::Board;
+    use std::{fs, path::PathBuf};
+
+    const FENS: [&str; 4] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/8/4k3/8/8/4K3/4P3/8 w - - 0 60",
+    ];
+
+    fn positions() -> Vec<PackedBoard> {
+        FENS.iter()
+            .enumerate()
+            .map(|(i, fen)| {
+                let board: Board = fen.parse().unwrap();
+                PackedBoard::pack(&board, i as i16 * 10, (i % 3) as u8)
+            })
+            .collect()
+    }
+
+    // A record whose only piece has an unknown piece code.
+    fn invalid() -> PackedBoard {
+        let mut bytes = [0u8; PackedBoard::SIZE];
+        bytes[0] = 1;
+        bytes[8] = 7;
+        PackedBoard::from_bytes(&bytes)
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("honse-data-{}-{name}", std::process::id()))
+    }
+
+    fn write(name: &str, positions: &[PackedBoard]) -> String {
+        let path = path(name).to_string_lossy().into_owned();
+        write_all(&path, positions.iter().copied()).unwrap();
+        path
+    }
+
+    fn read(path: &str) -> Vec<PackedBoard> {
+        let positions = read_all(path).unwrap();
+        fs::remove_file(path).unwrap();
+        positions
+    }
+
+    fn sorted(positions: Vec<PackedBoard>) -> Vec<[u8; PackedBoard::SIZE]> {
+        let mut bytes: Vec<_> = positions.into_iter().map(PackedBoard::to_bytes).collect();
+        bytes.sort();
+        bytes
+    }
+
+    #[test]
+    fn shuffle_round_trip() {
+        let input = write("shuffle.bin", &positions());
+        let output = path("shuffled.bin").to_string_lossy().into_owned();
+
+        shuffle(&input, &output, &mut Rng::new(3)).unwrap();
+        assert_eq!(sorted(read(&output)), sorted(read(&input)));
+    }
+
+    #[test]
+    fn dedupe_round_trip() {
+        let mut input = positions();
+        input.push(input[1]);
+        input.push(invalid());
+        input.push(input[0]);
+        assert_eq!(unique(input.clone()), (positions(), 2, 1));
+
+        let input = write("dedupe.bin", &input);
+        let output = path("deduped.bin").to_string_lossy().into_owned();
+        dedupe(&input, &output).unwrap();
+        fs::remove_file(input).unwrap();
+        assert_eq!(read(&output), positions());
+    }
+
+    #[test]
+    fn interleave_round_trip() {
+        let all = positions();
+        let (first, second) = all.split_at(2);
+        let inputs = [write("first.bin", first), write("second.bin", second)];
+        let output = path("interleaved.bin").to_string_lossy().into_owned();
+
+        interleave(&output, &inputs, &mut Rng::new(5)).unwrap();
+        let interleaved = read(&output);
+        assert_eq!(sorted(interleaved.clone()), sorted(all.clone()));
+
+        // Each input keeps its order.
+        for (input, positions) in inputs.iter().zip([first, second]) {
+            let kept: Vec<_> = interleaved
+                .iter()
+                .filter(|packed| positions.contains(packed))
+                .copied()
+                .collect();
+            assert_eq!(kept, read(input));
+        }
+    }
+
+    #[test]
+    fn stats_round_trip() {
+        let mut input = positions();
+        input.push(invalid());
+        let input = write("stats.bin", &input);
+
+        let stats = collect_stats(&[input.clone(), input.clone()]).unwrap();
+        fs::remove_file(input).unwrap();
+        assert_eq!((stats.count, stats.invalid), (8, 2));
+        // Results 0, 1, 2, 0 per file
+        assert_eq!(stats.wdl, [4, 2, 2]);
+        assert_eq!((stats.phases[24], stats.phases[0]), (6, 2));
+    }
+}
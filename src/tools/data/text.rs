@@ -0,0 +1,110 @@
+use crate::chess::packed::PackedBoard;
+use cozy_chess::Board;
+
+fn parse_result(s: &str) -> Option<f32> {
+    let s = s
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | ';' | '[' | ']' | ' '));
+
+    match s {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => s.parse().ok().filter(|r| (0.0..=1.0).contains(r)),
+    }
+}
+
+fn parse_fen(fen: &str) -> Option<Board> {
+    let fen = fen.trim();
+
+    fen.parse()
+        .ok()
+        .or_else(|| format!("{fen} 0 1").parse().ok())
+}
+
+// Accepts `<fen> | <score> | <result>`, `<fen> [<result>]` and `<fen> c9 "<result>";`.
+// Results are from white's point of view, scores are white relative centipawns.
+pub fn parse_line(line: &str) -> Option<(Board, f32, Option<f32>)> {
+    if line.contains('|') {
+        let parts: Vec<&str> = line.split('|').collect();
+        let score = match parts.len() {
+            2 => None,
+            3 => Some(parts[1].trim().parse().ok()?),
+            _ => return None,
+        };
+        return Some((
+            parse_fen(parts[0])?,
+            parse_result(parts[parts.len() - 1])?,
+            score,
+        ));
+    }
+
+    let split = line.find('[').or_else(|| line.find(" c9 "))?;
+    let (fen, result) = line.split_at(split);
+    let result = result.trim_start().trim_start_matches("c9");
+
+    Some((parse_fen(fen)?, parse_result(result)?, None))
+}
+
+// Results are rounded to a win, draw or loss, and a missing score is stored as 0.
+pub fn to_packed(line: &str) -> Option<PackedBoard> {
+    let (board, result, score) = parse_line(line)?;
+    let score = score.unwrap_or(0.0).clamp(i16::MIN as f32, i16::MAX as f32);
+
+    Some(PackedBoard::pack(
+        &board,
+        score as i16,
+        (result * 2.0).round() as u8,
+    ))
+}
+
+pub fn from_packed(packed: PackedBoard) -> Option<String> {
+    let board = packed.unpack()?;
+    let result = match packed.wdl {
+        0 => "0.0",
+        1 => "0.5",
+        2 => "1.0",
+        _ => return None,
+    };
+
+    Some(format!("{board} | {} | {result}", packed.score))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        let tests: [(String, f32, Option<f32>); 5] = [
+            (format!("{fen} | 35 | 1.0"), 1.0, Some(35.0)),
+            (format!("{fen} | 0.5"), 0.5, None),
+            (format!("{fen} [0.0]"), 0.0, None),
+            (format!("{fen} | -12 | 0-1"), 0.0, Some(-12.0)),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 \"1/2-1/2\";"
+                    .to_string(),
+                0.5,
+                None,
+            ),
+        ];
+
+        for (line, result, score) in tests {
+            let parsed = parse_line(&line).unwrap();
+            assert_eq!((parsed.1, parsed.2), (result, score), "{line}");
+        }
+
+        assert!(parse_line("not a fen | 1.0").is_none());
+        assert!(parse_line(&format!("{fen} [2.0]")).is_none());
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        let line = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 | -12 | 0.5";
+        let packed = to_packed(line).unwrap();
+
+        assert_eq!((packed.score, packed.wdl), (-12, 1));
+        assert_eq!(from_packed(packed).unwrap(), line);
+    }
+}
@@ -0,0 +1,337 @@
+use super::rng::Rng;
+use crate::{
+    chess::packed::PackedBoard,
+    search::{
+        definitions::{MATE, MAX_PLY},
+        options::SearchOptions,
+        search::{search_root, Output, SearchInfo},
+    },
+};
+use cozy_chess::{Board, Color, GameStatus, Move, Piece};
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+// Both sides have to agree on a decisive score for this many plies in a row.
+const WIN_SCORE: i16 = 1000;
+const WIN_PLIES: usize = 4;
+// From ply DRAW_PLY of the game, counted with the fullmove number rather than from
+// the opening, a game is drawn once the score stays close to zero.
+const DRAW_PLY: usize = 80;
+const DRAW_SCORE: i16 = 10;
+const DRAW_PLIES: usize = 8;
+const MAX_GAME_PLIES: usize = 400;
+
+struct DatagenOptions {
+    output: String,
+    games: usize,
+    threads: usize,
+    nodes: u64,
+    random_plies: usize,
+    seed: Option<u64>,
+}
+
+fn parse_args(args: &[String]) -> Option<DatagenOptions> {
+    let mut options = DatagenOptions {
+        output: String::new(),
+        games: 1000,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        nodes: 5000,
+        random_plies: 8,
+        seed: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => options.games = args.next()?.parse().ok()?,
+            "--threads" => options.threads = args.next()?.parse::<usize>().ok()?.max(1),
+            "--nodes" => options.nodes = args.next()?.parse::<u64>().ok()?.max(1),
+            "--random-plies" => options.random_plies = args.next()?.parse().ok()?,
+            "--seed" => options.seed = Some(args.next()?.parse().ok()?),
+            path => options.output = path.to_string(),
+        }
+    }
+
+    (!options.output.is_empty()).then_some(options)
+}
+
+// Plays random moves from the start position, retrying if the game ends on the way.
+fn random_opening(rng: &mut Rng, plies: usize) -> Board {
+    'retry: loop {
+        let mut board = Board::startpos();
+
+        for _ in 0..plies {
+            let mut moves = Vec::new();
+            board.generate_moves(|mvs| {
+                moves.extend(mvs);
+                false
+            });
+            if moves.is_empty() {
+                continue 'retry;
+            }
+            board.play_unchecked(moves[rng.below(moves.len())]);
+        }
+
+        if board.status() == GameStatus::Ongoing {
+            return board;
+        }
+    }
+}
+
+// Counts the plies in a row with a decisive score for the same side, or with a
+// score close to zero.
+#[derive(Default)]
+struct Adjudication {
+    win_plies: usize,
+    // The sign of the decisive scores counted in `win_plies`
+    winner: i16,
+    draw_plies: usize,
+}
+
+impl Adjudication {
+    // Takes the white score of the position at `ply` and returns the result, as in
+    // `play_game`, once the game can be adjudicated.
+    fn update(&mut self, score: i16, ply: usize) -> Option<u8> {
+        if score.abs() >= WIN_SCORE {
+            let winner = score.signum();
+            self.win_plies = if winner == self.winner {
+                self.win_plies + 1
+            } else {
+                1
+            };
+            self.winner = winner;
+        } else {
+            self.win_plies = 0;
+        }
+        if self.win_plies >= WIN_PLIES {
+            return Some(if self.winner > 0 { 2 } else { 0 });
+        }
+
+        self.draw_plies = if ply >= DRAW_PLY && score.abs() <= DRAW_SCORE {
+            self.draw_plies + 1
+        } else {
+            0
+        };
+        (self.draw_plies >= DRAW_PLIES).then_some(1)
+    }
+}
+
+// Plies played since the standard start position, going by the fullmove number.
+fn game_ply(board: &Board) -> usize {
+    (board.fullmove_number() as usize - 1) * 2 + (board.side_to_move() == Color::Black) as usize
+}
+
+// Positions where the best move wins material are not useful for training the
+// static evaluation.
+fn is_noisy(board: &Board, mv: Move) -> bool {
+    let en_passant = board.piece_on(mv.from) == Some(Piece::Pawn)
+        && mv.from.file() != mv.to.file()
+        && board.piece_on(mv.to).is_none();
+
+    !board.checkers().is_empty()
+        || board.color_on(mv.to) == Some(!board.side_to_move())
+        || en_passant
+        || mv.promotion.is_some()
+}
+
+// Plays one game and returns its quiet positions, labelled with the result.
+fn play_game(
+    info: &mut SearchInfo,
+    rng: &mut Rng,
+    nodes: u64,
+    random_plies: usize,
+) -> Vec<PackedBoard> {
+    let mut board = random_opening(rng, random_plies);
+    let mut hashes = vec![board.hash()];
+    let mut positions = Vec::new();
+    let mut adjudication = Adjudication::default();
+
+    // From white's point of view: 0 black win, 1 draw, 2 white win.
+    let wdl = loop {
+        let repetitions = hashes.iter().filter(|&&h| h == board.hash()).count();
+
+        match board.status() {
+            GameStatus::Won if board.side_to_move() == Color::White => break 0,
+            GameStatus::Won => break 2,
+            GameStatus::Drawn => break 1,
+            GameStatus::Ongoing if repetitions >= 3 || hashes.len() > MAX_GAME_PLIES => break 1,
+            GameStatus::Ongoing => (),
+        }
+
+        info.reset();
+        let result = search_root(info, &board, SearchOptions::Nodes(nodes), false)
+            .expect("ongoing games have legal moves");
+        let score = match board.side_to_move() {
+            Color::White => result.score,
+            Color::Black => -result.score,
+        };
+
+        if let Some(wdl) = adjudication.update(score, game_ply(&board)) {
+            break wdl;
+        }
+
+        if !is_noisy(&board, result.best_move) && score.abs() < MATE - MAX_PLY as i16 {
+            positions.push(PackedBoard::pack(&board, score, 0));
+        }
+
+        board.play(result.best_move);
+        hashes.push(board.hash());
+    };
+
+    for position in &mut positions {
+        position.wdl = wdl;
+    }
+    positions
+}
+
+pub fn datagen(args: &[String]) {
+    let Some(options) = parse_args(args) else {
+        println!(
+            "Usage: honse datagen <output> [--games <n>] [--threads <n>] [--nodes <n>] [--random-plies <n>] [--seed <n>]"
+        );
+        return;
+    };
+
+    // Existing data is kept, so runs can be stopped and continued.
+    let file = match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&options.output)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Failed to open {}: {e}", options.output);
+            return;
+        }
+    };
+    let mut writer = BufWriter::new(file);
+
+    let mut seeder = options.seed.map_or_else(Rng::from_time, Rng::new);
+    let started = AtomicUsize::new(0);
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::channel::<Vec<PackedBoard>>();
+
+    thread::scope(|s| {
+        for _ in 0..options.threads {
+            let sender = sender.clone();
+            let mut rng = Rng::new(seeder.next_u64());
+            let (started, options) = (&started, &options);
+
+            s.spawn(move || {
+                let mut info = SearchInfo::new();
+                info.output = Output::Silent;
+
+                while started.fetch_add(1, Ordering::Relaxed) < options.games {
+                    let positions =
+                        play_game(&mut info, &mut rng, options.nodes, options.random_plies);
+                    if sender.send(positions).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let (mut games, mut total) = (0, 0);
+        for positions in receiver {
+            for position in &positions {
+                if let Err(e) = position.write(&mut writer) {
+                    println!("Failed to write {}: {e}", options.output);
+                    std::process::exit(1);
+                }
+            }
+
+            games += 1;
+            total += positions.len();
+            if games % 100 == 0 || games == options.games {
+                let elapsed = start.elapsed().as_secs().max(1);
+                println!(
+                    "Games {games}/{} positions {total} ({} positions/s)",
+                    options.games,
+                    total as u64 / elapsed
+                );
+            }
+        }
+    });
+
+    if let Err(e) = writer.flush() {
+        println!("Failed to write {}: {e}", options.output);
+        return;
+    }
+    println!("Wrote {}", options.output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_labelled_positions() {
+        let mut info = SearchInfo::new();
+        info.output = Output::Silent;
+        let mut rng = Rng::new(1);
+
+        for _ in 0..2 {
+            let positions = play_game(&mut info, &mut rng, 100, 8);
+            let wdl = positions.first().map_or(1, |p| p.wdl);
+
+            for position in positions {
+                let board = position.unpack().unwrap();
+                assert!(board.checkers().is_empty());
+                assert_eq!(position.wdl, wdl);
+                assert!(wdl <= 2);
+            }
+        }
+    }
+
+    #[test]
+    fn adjudication() {
+        // Decisive scores for alternating sides are not a win.
+        let mut adjudication = Adjudication::default();
+        for score in [1200, -1100, 1300, -1200, 1100, -1300, 1200] {
+            assert_eq!(adjudication.update(score, 10), None);
+        }
+        let results: Vec<_> = [-1100, -1200, -1300, -1400]
+            .iter()
+            .map(|&score| adjudication.update(score, 10))
+            .collect();
+        assert_eq!(results, [None, None, None, Some(0)]);
+
+        // A sign flip starts the count again.
+        let mut adjudication = Adjudication::default();
+        for score in [1200, 1200, 1200, -1200, 1200, 1200, 1200] {
+            assert_eq!(adjudication.update(score, 10), None);
+        }
+        assert_eq!(adjudication.update(1200, 10), Some(2));
+
+        // Draws only from DRAW_PLY on.
+        let mut adjudication = Adjudication::default();
+        for ply in 0..DRAW_PLY {
+            assert_eq!(adjudication.update(0, ply), None);
+        }
+        for ply in DRAW_PLY..DRAW_PLY + DRAW_PLIES - 1 {
+            assert_eq!(adjudication.update(5, ply), None);
+        }
+        assert_eq!(adjudication.update(-5, DRAW_PLY + DRAW_PLIES), Some(1));
+    }
+
+    #[test]
+    fn ply_of_the_game() {
+        assert_eq!(game_ply(&Board::startpos()), 0);
+
+        let board: Board = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse()
+            .unwrap();
+        assert_eq!(game_ply(&board), 4);
+        let board: Board = "8/8/4k3/8/8/4K3/8/8 b - - 0 60".parse().unwrap();
+        assert_eq!(game_ply(&board), 119);
+    }
+}
@@ -0,0 +1,461 @@
+use crate::{
+    chess::{epd::Epd, parse_move::move_to_san},
+    search::{
+        definitions::{MATE, MAX_PLY},
+        options::SearchOptions,
+        search::{search_root, Output, SearchInfo, SearchResult},
+    },
+};
+use cozy_chess::{Board, Move};
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Instant,
+};
+
+struct EpdOptions {
+    path: String,
+    limit: SearchOptions,
+    threads: usize,
+    // Write the engine's analysis to this file instead of scoring the suite.
+    annotate: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Option<EpdOptions> {
+    let mut options = EpdOptions {
+        path: String::new(),
+        limit: SearchOptions::Movetime(1000),
+        threads: 1,
+        annotate: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--movetime" => options.limit = SearchOptions::Movetime(args.next()?.parse().ok()?),
+            "--depth" => options.limit = SearchOptions::Depth(args.next()?.parse().ok()?),
+            "--nodes" => options.limit = SearchOptions::Nodes(args.next()?.parse().ok()?),
+            "--threads" => options.threads = args.next()?.parse::<usize>().ok()?.max(1),
+            "--annotate" => options.annotate = Some(args.next()?.clone()),
+            path => options.path = path.to_string(),
+        }
+    }
+
+    (!options.path.is_empty()).then_some(options)
+}
+
+struct Outcome {
+    id: String,
+    passed: bool,
+    best_move: String,
+    expected: String,
+    depth: u8,
+    // The first depth from which every iteration picked a correct move.
+    solved_at: Option<u8>,
+    pv: String,
+    ms: u128,
+    comment: Option<String>,
+}
+
+// Moves until mate if the score is a mate score.
+fn mate_moves(score: i16) -> Option<i16> {
+    (score >= MATE - MAX_PLY as i16).then(|| (MATE - score + 1) / 2)
+}
+
+fn san_line(board: &Board, moves: &[Move]) -> String {
+    let mut board = board.clone();
+    let mut line = Vec::new();
+    for &mv in moves {
+        line.push(move_to_san(&board, mv));
+        board.play_unchecked(mv);
+    }
+    line.join(" ")
+}
+
+fn score(epd: &Epd, result: &SearchResult) -> Option<(bool, Option<u8>)> {
+    let bm = epd.moves("bm");
+    let am = epd.moves("am");
+    let dm = epd
+        .op("dm")
+        .and_then(|operands| operands.first()?.parse::<i16>().ok());
+
+    if bm.is_empty() && am.is_empty() && dm.is_none() {
+        return None;
+    }
+
+    let correct = |mv: &Move| (bm.is_empty() || bm.contains(mv)) && !am.contains(mv);
+    let mate_found = dm.is_none_or(|n| mate_moves(result.score).is_some_and(|m| m <= n));
+    let passed = correct(&result.best_move) && mate_found;
+
+    let solved_at = if bm.is_empty() && am.is_empty() {
+        None
+    } else {
+        let wrong = result.best_moves.iter().rposition(|mv| !correct(mv));
+        let first = wrong.map_or(0, |i| i + 1);
+        (first < result.best_moves.len()).then_some(first as u8 + 1)
+    };
+
+    Some((passed, solved_at))
+}
+
+// Returns why the position was skipped instead of an outcome if it can't be scored.
+fn run(epd: &Epd, info: &mut SearchInfo, limit: SearchOptions) -> Result<Outcome, &'static str> {
+    let id = epd
+        .op("id")
+        .and_then(|operands| operands.first().cloned())
+        .unwrap_or_default();
+    let expected: Vec<String> = ["bm", "am", "dm"]
+        .iter()
+        .filter_map(|&opcode| Some(format!("{opcode} {}", epd.op(opcode)?.join(" "))))
+        .collect();
+
+    info.reset();
+    let start = Instant::now();
+    let result = search_root(info, &epd.board, limit, false).ok_or("no legal moves")?;
+    let ms = start.elapsed().as_millis();
+    let (passed, solved_at) = score(epd, &result).ok_or("no bm, am or dm operation")?;
+
+    Ok(Outcome {
+        id,
+        passed,
+        best_move: move_to_san(&epd.board, result.best_move),
+        expected: expected.join(", "),
+        depth: result.depth,
+        solved_at,
+        pv: san_line(&epd.board, &result.pv),
+        ms,
+        comment: epd.op("c0").and_then(|operands| operands.first().cloned()),
+    })
+}
+
+fn report_line(index: usize, outcome: &Outcome) -> String {
+    let solved = outcome
+        .solved_at
+        .map_or(String::from("-"), |depth| depth.to_string());
+
+    format!(
+        "{:>4} {:<12} {} {:<8} ({}) depth {:>2} solved at {:>2} {:>6} ms",
+        index + 1,
+        outcome.id,
+        if outcome.passed { "pass" } else { "FAIL" },
+        outcome.best_move,
+        outcome.expected,
+        outcome.depth,
+        solved,
+        outcome.ms
+    )
+}
+
+// Searches the positions on `threads` threads, passing each result to `done` in the
+// order they finish.
+fn parallel<T: Send>(
+    positions: &[Epd],
+    threads: usize,
+    work: impl Fn(&Epd, &mut SearchInfo) -> T + Sync,
+    mut done: impl FnMut(usize, T),
+) {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|s| {
+        for _ in 0..threads.min(positions.len()) {
+            let sender = sender.clone();
+            let (next, work) = (&next, &work);
+
+            s.spawn(move || {
+                let mut info = SearchInfo::new();
+                info.output = Output::Silent;
+
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(epd) = positions.get(index) else {
+                        return;
+                    };
+                    if sender.send((index, work(epd, &mut info))).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, result) in receiver {
+            done(index, result);
+        }
+    });
+}
+
+fn run_suite(positions: &[Epd], options: &EpdOptions) {
+    let mut outcomes: Vec<Option<Outcome>> = Vec::new();
+    outcomes.resize_with(positions.len(), || None);
+
+    parallel(
+        positions,
+        options.threads,
+        |epd, info| run(epd, info, options.limit),
+        |index, outcome| {
+            match &outcome {
+                Ok(outcome) => println!("{}", report_line(index, outcome)),
+                Err(reason) => println!("{:>4} skipped, {reason}", index + 1),
+            }
+            outcomes[index] = outcome.ok();
+        },
+    );
+
+    let scored: Vec<(usize, &Outcome)> = outcomes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, outcome)| Some((index, outcome.as_ref()?)))
+        .collect();
+    let passed = scored.iter().filter(|(_, outcome)| outcome.passed).count();
+
+    let failures: Vec<_> = scored
+        .iter()
+        .filter(|(_, outcome)| !outcome.passed)
+        .collect();
+    if !failures.is_empty() {
+        println!("\nFailures:");
+        for (index, outcome) in failures {
+            println!("{}", report_line(*index, outcome));
+            println!("       pv {}", outcome.pv);
+            if let Some(comment) = &outcome.comment {
+                println!("       {comment}");
+            }
+        }
+    }
+
+    println!(
+        "\nPassed {passed}/{} ({:.1}%)",
+        scored.len(),
+        100.0 * passed as f64 / scored.len().max(1) as f64
+    );
+}
+
+// Fills in the engine's analysis, keeping any other operations. None for positions
+// without legal moves.
+fn annotated(epd: &Epd, info: &mut SearchInfo, limit: SearchOptions) -> Option<Epd> {
+    info.reset();
+    let result = search_root(info, &epd.board, limit, false)?;
+
+    let mut epd = epd.clone();
+    epd.set("ce", vec![result.score.to_string()]);
+    epd.set("acd", vec![result.depth.to_string()]);
+    epd.set("acn", vec![info.nodes.to_string()]);
+    epd.set(
+        "pv",
+        san_line(&epd.board, &result.pv)
+            .split(' ')
+            .map(String::from)
+            .collect(),
+    );
+    epd.set("bm", vec![move_to_san(&epd.board, result.best_move)]);
+    Some(epd)
+}
+
+// Appends to `output` as positions finish, skipping the ones it already has, so an
+// interrupted run can be continued.
+fn annotate(positions: &[Epd], output: &str, options: &EpdOptions) -> io::Result<()> {
+    let done: HashSet<u64> = match fs::read_to_string(output) {
+        Ok(contents) => {
+            // A run stopped mid-write can leave an incomplete last line behind.
+            let complete = contents.rfind('\n').map_or(0, |i| i + 1);
+            if complete < contents.len() {
+                let file = OpenOptions::new().write(true).open(output)?;
+                file.set_len(complete as u64)?;
+                println!("Removed an incomplete last line from {output}");
+            }
+
+            contents[..complete]
+                .lines()
+                .filter_map(Epd::parse)
+                .map(|epd| epd.board.hash())
+                .collect()
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+        Err(e) => return Err(e),
+    };
+    let todo: Vec<Epd> = positions
+        .iter()
+        .filter(|epd| !done.contains(&epd.board.hash()))
+        .cloned()
+        .collect();
+    if todo.len() < positions.len() {
+        println!(
+            "Skipping {} positions already in {output}",
+            positions.len() - todo.len()
+        );
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(output)?;
+    let (mut written, mut skipped) = (0, 0);
+    let mut error = None;
+
+    parallel(
+        &todo,
+        options.threads,
+        |epd, info| annotated(epd, info, options.limit),
+        |_, epd| {
+            let Some(epd) = epd else {
+                skipped += 1;
+                return;
+            };
+            if error.is_some() {
+                return;
+            }
+            match writeln!(file, "{epd}") {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    error = Some(e);
+                    return;
+                }
+            }
+            if written % 100 == 0 {
+                println!("Annotated {written}/{}", todo.len());
+            }
+        },
+    );
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if skipped > 0 {
+        println!("Skipped {skipped} positions without legal moves");
+    }
+    println!("Annotated {written} positions into {output}");
+    Ok(())
+}
+
+pub fn epd(args: &[String]) {
+    let Some(options) = parse_args(args) else {
+        println!(
+            "Usage: honse epd <file> [--movetime <ms>] [--depth <n>] [--nodes <n>] [--threads <n>] [--annotate <output>]"
+        );
+        return;
+    };
+
+    let contents = match fs::read_to_string(&options.path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            println!("Failed to read {}: {e}", options.path);
+            return;
+        }
+    };
+    let positions: Vec<Epd> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(Epd::parse)
+        .collect();
+
+    match &options.annotate {
+        Some(output) => {
+            if let Err(e) = annotate(&positions, output, &options) {
+                println!("Failed to write {output}: {e}");
+            }
+        }
+        None => run_suite(&positions, &options),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_suite() {
+        let suite = [
+            r#"4r2k/1p3rbp/2p1N1p1/p3n3/P2NB1nq/1P6/4R1P1/B1Q2RK1 b - - bm Qh2+; id "mate 1";"#,
+            r#"4r2k/1p3rbp/2p1N1p1/p3n3/P2NB1nq/1P6/4R1P1/B1Q2RK1 b - - dm 1; id "dm";"#,
+            r#"4r2k/1p3rbp/2p1N1p1/p3n3/P2NB1nq/1P6/4R1P1/B1Q2RK1 b - - am Qh2; id "avoid";"#,
+        ];
+        let mut info = SearchInfo::new();
+        info.output = Output::Silent;
+
+        let outcomes: Vec<Outcome> = suite
+            .iter()
+            .map(|line| {
+                run(
+                    &Epd::parse(line).unwrap(),
+                    &mut info,
+                    SearchOptions::Depth(3),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        assert!(outcomes[0].passed);
+        assert_eq!(outcomes[0].best_move, "Qh2#");
+        assert_eq!(outcomes[0].id, "mate 1");
+        assert!(outcomes[0].solved_at.is_some_and(|depth| depth <= 3));
+        assert!(outcomes[1].passed);
+        assert!(!outcomes[2].passed);
+        assert_eq!(outcomes[2].solved_at, None);
+
+        // Mated and stalemated positions are skipped.
+        for line in [
+            "R5k1/5ppp/8/8/8/8/8/6K1 b - - bm Kh8;",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - bm Kh8;",
+        ] {
+            let epd = Epd::parse(line).unwrap();
+            let reason = run(&epd, &mut info, SearchOptions::Depth(2)).err();
+            assert_eq!(reason, Some("no legal moves"), "{line}");
+        }
+    }
+
+    #[test]
+    fn annotations() {
+        let mut info = SearchInfo::new();
+        info.output = Output::Silent;
+        let epd = Epd::parse(
+            r#"4r2k/1p3rbp/2p1N1p1/p3n3/P2NB1nq/1P6/4R1P1/B1Q2RK1 b - - 4 32 bm Qf6; id "x";"#,
+        )
+        .unwrap();
+
+        let epd = annotated(&epd, &mut info, SearchOptions::Depth(3)).unwrap();
+        assert_eq!(epd.op("bm"), Some(["Qh2#".to_string()].as_slice()));
+        assert_eq!(epd.op("acd"), Some(["3".to_string()].as_slice()));
+        assert_eq!(epd.op("pv"), Some(["Qh2#".to_string()].as_slice()));
+        assert_eq!(epd.op("id"), Some(["x".to_string()].as_slice()));
+        assert!(epd.op("ce").is_some() && epd.op("acn").is_some());
+    }
+
+    #[test]
+    fn resume_annotation() {
+        let output =
+            std::env::temp_dir().join(format!("honse-annotate-{}.epd", std::process::id()));
+        let output = output.to_string_lossy().into_owned();
+        let args: Vec<String> = ["suite.epd", "--depth", "2", "--annotate", &output]
+            .map(String::from)
+            .to_vec();
+        let options = parse_args(&args).unwrap();
+
+        let positions: Vec<Epd> = [
+            r#"4r2k/1p3rbp/2p1N1p1/p3n3/P2NB1nq/1P6/4R1P1/B1Q2RK1 b - - id "done";"#,
+            r#"R5k1/5ppp/8/8/8/8/8/6K1 b - - id "mated";"#,
+            r#"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id "e4";"#,
+        ]
+        .iter()
+        .map(|line| Epd::parse(line).unwrap())
+        .collect();
+
+        // The first position is done, and a partial line of the third one was left.
+        let partial = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - ce 1";
+        fs::write(&output, format!("{}\n{partial}", positions[0])).unwrap();
+
+        annotate(&positions, &output, &options).unwrap();
+        let contents = fs::read_to_string(&output).unwrap();
+        fs::remove_file(&output).unwrap();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2, "{contents}");
+        assert_eq!(lines[0], positions[0].to_string());
+        let e4 = Epd::parse(lines[1]).unwrap();
+        assert_eq!(e4.op("id"), Some(["e4".to_string()].as_slice()));
+        assert_eq!(e4.op("acd"), Some(["2".to_string()].as_slice()));
+    }
+}
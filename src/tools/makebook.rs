@@ -0,0 +1,244 @@
+use crate::{
+    book::{encode_move, keys::polyglot_key, BookEntry},
+    chess::pgn::{Game, GameResult, PgnReader},
+};
+use cozy_chess::Color;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+};
+
+struct MakeBookOptions {
+    inputs: Vec<String>,
+    output: String,
+    max_ply: usize,
+    min_games: u32,
+    // Points for the side that played the move.
+    win: u32,
+    draw: u32,
+    loss: u32,
+    // Only moves played by this side.
+    color: Option<Color>,
+}
+
+fn parse_args(args: &[String]) -> Option<MakeBookOptions> {
+    let mut options = MakeBookOptions {
+        inputs: Vec::new(),
+        output: String::new(),
+        max_ply: 24,
+        min_games: 3,
+        win: 2,
+        draw: 1,
+        loss: 0,
+        color: None,
+    };
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-ply" => options.max_ply = args.next()?.parse().ok()?,
+            "--min-games" => options.min_games = args.next()?.parse().ok()?,
+            "--win" => options.win = args.next()?.parse().ok()?,
+            "--draw" => options.draw = args.next()?.parse().ok()?,
+            "--loss" => options.loss = args.next()?.parse().ok()?,
+            "--only-white" => options.color = Some(Color::White),
+            "--only-black" => options.color = Some(Color::Black),
+            path => paths.push(path.to_string()),
+        }
+    }
+
+    options.output = paths.pop()?;
+    options.inputs = paths;
+    (!options.inputs.is_empty()).then_some(options)
+}
+
+#[derive(Default)]
+struct Stats {
+    games: u32,
+    points: u64,
+}
+
+#[derive(Default)]
+struct BookBuilder {
+    // Indexed by Polyglot key and encoded move.
+    moves: HashMap<(u64, u16), Stats>,
+}
+
+impl BookBuilder {
+    fn add(&mut self, game: &Game, options: &MakeBookOptions) {
+        // Unfinished games say nothing about which moves are good.
+        let Some(result) = game.result else {
+            return;
+        };
+        // Polyglot keys only describe standard chess.
+        if game
+            .tag("Variant")
+            .is_some_and(|variant| !variant.eq_ignore_ascii_case("standard"))
+        {
+            return;
+        }
+
+        for (board, &mv) in game.boards().zip(&game.moves).take(options.max_ply) {
+            let stm = board.side_to_move();
+
+            if options.color.is_none_or(|color| color == stm) {
+                let points = match (result, stm) {
+                    (GameResult::Draw, _) => options.draw,
+                    (GameResult::WhiteWin, Color::White) | (GameResult::BlackWin, Color::Black) => {
+                        options.win
+                    }
+                    _ => options.loss,
+                };
+
+                let stats = self
+                    .moves
+                    .entry((polyglot_key(&board), encode_move(mv)))
+                    .or_default();
+                stats.games += 1;
+                stats.points += points as u64;
+            }
+        }
+    }
+
+    // Sorted by key and then weight, as the format requires.
+    fn entries(&self, min_games: u32) -> Vec<BookEntry> {
+        let moves: Vec<_> = self
+            .moves
+            .iter()
+            .filter(|(_, stats)| stats.games >= min_games && stats.points > 0)
+            .collect();
+
+        // Weights are 16 bit, so scale everything down if the largest would not fit.
+        let largest = moves.iter().map(|(_, s)| s.points).max().unwrap_or(0);
+        let scale = largest.div_ceil(u16::MAX as u64).max(1);
+
+        let mut entries: Vec<BookEntry> = moves
+            .into_iter()
+            .map(|(&(key, mv), stats)| BookEntry {
+                key,
+                mv,
+                weight: (stats.points / scale).max(1) as u16,
+                learn: 0,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+
+        entries
+    }
+}
+
+pub fn makebook(args: &[String]) {
+    let Some(options) = parse_args(args) else {
+        println!(
+            "Usage: honse makebook <pgn>... <output> [--max-ply <n>] [--min-games <n>] [--win <points>] [--draw <points>] [--loss <points>] [--only-white] [--only-black]"
+        );
+        return;
+    };
+
+    let mut builder = BookBuilder::default();
+    let (mut games, mut skipped) = (0, 0);
+
+    for path in &options.inputs {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                println!("Failed to open {path}: {e}");
+                return;
+            }
+        };
+
+        for game in PgnReader::new(BufReader::new(file)) {
+            match game {
+                Ok(game) => {
+                    builder.add(&game, &options);
+                    games += 1;
+                }
+                Err(e) => {
+                    skipped += 1;
+                    if skipped <= 10 {
+                        println!("Skipping game in {path}: {e}");
+                    }
+                }
+            }
+        }
+    }
+
+    let entries = builder.entries(options.min_games);
+    let write = || -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&options.output)?);
+        for entry in &entries {
+            writer.write_all(&entry.to_bytes())?;
+        }
+        writer.flush()
+    };
+
+    match write() {
+        Ok(()) => println!(
+            "Read {games} games ({skipped} skipped), wrote {} entries to {}",
+            entries.len(),
+            options.output
+        ),
+        Err(e) => println!("Failed to write {}: {e}", options.output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{book::Book, tools::rng::Rng};
+    use cozy_chess::Board;
+
+    const PGN: &str = "
+1. e4 e5 2. Nf3 1-0
+1. e4 c5 2. Nf3 1/2-1/2
+1. d4 d5 0-1
+1. e4 e5 2. Bc4 *
+";
+
+    fn build(args: &[&str]) -> Book {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let options = parse_args(&args).unwrap();
+        let mut builder = BookBuilder::default();
+        for game in PgnReader::new(PGN.as_bytes()) {
+            builder.add(&game.unwrap(), &options);
+        }
+
+        let bytes: Vec<u8> = builder
+            .entries(options.min_games)
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect();
+        Book::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let book = build(&["games.pgn", "out.bin", "--min-games", "1"]);
+        let mut board = Board::startpos();
+
+        // e4: a win and a draw, d4: a loss.
+        assert_eq!(book.moves(&board), [("e2e4".parse().unwrap(), 3)]);
+        assert_eq!(
+            book.probe(&board, true, &mut Rng::new(0)),
+            Some("e2e4".parse().unwrap())
+        );
+
+        // e5 only lost, and the game ending in `*` is not counted.
+        board.play("e2e4".parse().unwrap());
+        assert_eq!(book.moves(&board), [("c7c5".parse().unwrap(), 1)]);
+    }
+
+    #[test]
+    fn filters() {
+        let book = build(&["games.pgn", "out.bin", "--min-games", "2"]);
+        assert_eq!(book.num_entries(), 1);
+
+        let book = build(&["games.pgn", "out.bin", "--min-games", "1", "--only-black"]);
+        assert!(book.moves(&Board::startpos()).is_empty());
+
+        let book = build(&["games.pgn", "out.bin", "--min-games", "1", "--max-ply", "2"]);
+        assert_eq!(book.num_entries(), 3);
+    }
+}
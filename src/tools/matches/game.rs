@@ -0,0 +1,420 @@
+use crate::{
+    chess::{
+        parse_move::{move_to_string, parse_move},
+        pgn::GameResult,
+    },
+    uci::client::{Engine, Score},
+};
+use cozy_chess::{BitBoard, Board, Color, Move, Piece};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+// How far past its clock an engine may go before it loses on time.
+const TIME_MARGIN: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    // `<seconds>[+<increment seconds>]`, e.g. `10+0.1`
+    pub fn parse(s: &str) -> Option<TimeControl> {
+        let (base, increment) = s.split_once('+').unwrap_or((s, "0"));
+        let seconds = |s: &str| {
+            s.parse::<f64>()
+                .ok()
+                .filter(|&x| x >= 0.0 && x.is_finite())
+                .map(Duration::from_secs_f64)
+        };
+
+        Some(TimeControl {
+            base: seconds(base)?,
+            increment: seconds(increment)?,
+        })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}+{}",
+            self.base.as_secs_f64(),
+            self.increment.as_secs_f64()
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Adjudication {
+    // A side resigns after this many consecutive moves scored at or below -resign_score.
+    pub resign_moves: usize,
+    pub resign_score: i32,
+    // From this full move, a game is drawn after this many consecutive plies scored
+    // within draw_score of zero.
+    pub draw_after: u16,
+    pub draw_moves: usize,
+    pub draw_score: i32,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            resign_moves: 3,
+            resign_score: 1000,
+            draw_after: 40,
+            draw_moves: 8,
+            draw_score: 10,
+        }
+    }
+}
+
+impl Adjudication {
+    // `scores` holds the score of every played move, from the point of view of the
+    // side that played it, and `mover` is the side that played the last one.
+    fn adjudicate(
+        &self,
+        scores: &[Option<i32>],
+        fullmove: u16,
+        mover: Color,
+    ) -> Option<GameResult> {
+        let own: Vec<_> = scores
+            .iter()
+            .rev()
+            .step_by(2)
+            .take(self.resign_moves)
+            .collect();
+        let resigns = self.resign_moves > 0
+            && own.len() == self.resign_moves
+            && own
+                .iter()
+                .all(|score| score.is_some_and(|s| s <= -self.resign_score));
+        if resigns {
+            return Some(match mover {
+                Color::White => GameResult::BlackWin,
+                Color::Black => GameResult::WhiteWin,
+            });
+        }
+
+        let recent = &scores[scores.len().saturating_sub(self.draw_moves)..];
+        let drawn = self.draw_moves > 0
+            && fullmove >= self.draw_after
+            && recent.len() == self.draw_moves
+            && recent
+                .iter()
+                .all(|score| score.is_some_and(|s| s.abs() <= self.draw_score));
+        drawn.then_some(GameResult::Draw)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    Repetition,
+    FiftyMoves,
+    InsufficientMaterial,
+    Resignation,
+    DrawAdjudication,
+    TimeForfeit,
+    IllegalMove,
+    Disconnect,
+}
+
+impl Termination {
+    // The value of the PGN Termination tag
+    pub fn tag(self) -> &'static str {
+        match self {
+            Termination::Resignation | Termination::DrawAdjudication => "adjudication",
+            Termination::TimeForfeit => "time forfeit",
+            Termination::IllegalMove => "rules infraction",
+            Termination::Disconnect => "emergency",
+            _ => "normal",
+        }
+    }
+
+    // Whether the engines may be left in a state where they cannot play on.
+    pub fn needs_restart(self) -> bool {
+        matches!(self, Termination::TimeForfeit | Termination::Disconnect)
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::Repetition => "threefold repetition",
+            Termination::FiftyMoves => "fifty move rule",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Resignation => "resignation",
+            Termination::DrawAdjudication => "draw adjudication",
+            Termination::TimeForfeit => "time forfeit",
+            Termination::IllegalMove => "illegal move",
+            Termination::Disconnect => "disconnect",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+pub struct Opening {
+    pub start: Board,
+    pub moves: Vec<Move>,
+}
+
+pub struct GameRecord {
+    pub result: GameResult,
+    pub termination: Termination,
+    // The moves played by the engines, after the opening.
+    pub moves: Vec<Move>,
+}
+
+fn insufficient_material(board: &Board) -> bool {
+    let minors = board.pieces(Piece::Knight) | board.pieces(Piece::Bishop);
+    if board.occupied() != board.pieces(Piece::King) | minors {
+        return false;
+    }
+
+    // A lone minor piece, or bishops that all stand on squares of one color
+    let bishops = board.pieces(Piece::Bishop);
+    let light = BitBoard(0x55AA_55AA_55AA_55AA);
+    minors.len() <= 1
+        || (minors == bishops && ((bishops & light) == bishops || (bishops & light).is_empty()))
+}
+
+// The result by the rules of chess, with `history` holding the hash of every
+// position so far, including the current one.
+fn game_over(board: &Board, history: &[u64]) -> Option<(GameResult, Termination)> {
+    let loss = match board.side_to_move() {
+        Color::White => GameResult::BlackWin,
+        Color::Black => GameResult::WhiteWin,
+    };
+
+    if !board.generate_moves(|_| true) {
+        return Some(if board.checkers().is_empty() {
+            (GameResult::Draw, Termination::Stalemate)
+        } else {
+            (loss, Termination::Checkmate)
+        });
+    }
+
+    if board.halfmove_clock() >= 100 {
+        return Some((GameResult::Draw, Termination::FiftyMoves));
+    }
+    if history.iter().filter(|&&hash| hash == board.hash()).count() >= 3 {
+        return Some((GameResult::Draw, Termination::Repetition));
+    }
+    if insufficient_material(board) {
+        return Some((GameResult::Draw, Termination::InsufficientMaterial));
+    }
+
+    None
+}
+
+// Plays one game, with `engines` indexed by color.
+pub fn play_game(
+    mut engines: [&mut Engine; 2],
+    opening: &Opening,
+    time_control: TimeControl,
+    adjudication: Option<&Adjudication>,
+) -> GameRecord {
+    let start = opening.start.to_string();
+    let mut board = opening.start.clone();
+    let mut moves = Vec::new();
+    for &mv in &opening.moves {
+        moves.push(move_to_string(&board, mv));
+        board.play_unchecked(mv);
+    }
+
+    let mut record = GameRecord {
+        result: GameResult::Draw,
+        termination: Termination::Disconnect,
+        moves: Vec::new(),
+    };
+    let forfeit = |record: &mut GameRecord, loser: Color, termination| {
+        record.result = match loser {
+            Color::White => GameResult::BlackWin,
+            Color::Black => GameResult::WhiteWin,
+        };
+        record.termination = termination;
+    };
+
+    for (engine, color) in engines.iter_mut().zip(Color::ALL) {
+        if engine.new_game().is_err() {
+            forfeit(&mut record, color, Termination::Disconnect);
+            return record;
+        }
+    }
+
+    let mut clocks = [time_control.base; 2];
+    let mut history = vec![board.hash()];
+    let mut scores = Vec::new();
+
+    loop {
+        if let Some((result, termination)) = game_over(&board, &history) {
+            record.result = result;
+            record.termination = termination;
+            return record;
+        }
+
+        let stm = board.side_to_move();
+        let engine = &mut *engines[stm as usize];
+        let limits = format!(
+            "wtime {} btime {} winc {} binc {}",
+            clocks[0].as_millis(),
+            clocks[1].as_millis(),
+            time_control.increment.as_millis(),
+            time_control.increment.as_millis()
+        );
+
+        let started = Instant::now();
+        let reply = engine
+            .position(&start, &moves)
+            .and_then(|_| engine.go(&limits, clocks[stm as usize] + TIME_MARGIN));
+        let elapsed = started.elapsed();
+
+        let output = match reply {
+            Ok(reply) => reply,
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                forfeit(&mut record, stm, Termination::TimeForfeit);
+                return record;
+            }
+            Err(_) => {
+                forfeit(&mut record, stm, Termination::Disconnect);
+                return record;
+            }
+        };
+        if elapsed > clocks[stm as usize] + TIME_MARGIN {
+            forfeit(&mut record, stm, Termination::TimeForfeit);
+            return record;
+        }
+        clocks[stm as usize] =
+            clocks[stm as usize].saturating_sub(elapsed) + time_control.increment;
+
+        let mv = parse_move(&board, &output.best_move)
+            .ok()
+            .filter(|&mv| board.is_legal(mv));
+        let Some(mv) = mv else {
+            forfeit(&mut record, stm, Termination::IllegalMove);
+            return record;
+        };
+
+        moves.push(move_to_string(&board, mv));
+        board.play_unchecked(mv);
+        history.push(board.hash());
+        record.moves.push(mv);
+        scores.push(output.score().map(Score::to_cp));
+
+        // Checkmate and stalemate take precedence over adjudication.
+        if game_over(&board, &history).is_some() {
+            continue;
+        }
+        if let Some(result) =
+            adjudication.and_then(|x| x.adjudicate(&scores, board.fullmove_number(), stm))
+        {
+            record.result = result;
+            record.termination = match result {
+                GameResult::Draw => Termination::DrawAdjudication,
+                _ => Termination::Resignation,
+            };
+            return record;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_control() {
+        let tc = TimeControl::parse("10+0.1").unwrap();
+        assert_eq!(tc.base, Duration::from_secs(10));
+        assert_eq!(tc.increment, Duration::from_millis(100));
+        assert_eq!(tc.to_string(), "10+0.1");
+
+        assert_eq!(
+            TimeControl::parse("60"),
+            Some(TimeControl {
+                base: Duration::from_secs(60),
+                increment: Duration::ZERO,
+            })
+        );
+        assert_eq!(TimeControl::parse("x+1"), None);
+        assert_eq!(TimeControl::parse("-1"), None);
+    }
+
+    #[test]
+    fn rules() {
+        let over = |fen: &str| {
+            let board: Board = fen.parse().unwrap();
+            game_over(&board, &[board.hash()]).map(|(_, termination)| termination)
+        };
+
+        assert_eq!(
+            over("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            Some(Termination::Checkmate)
+        );
+        assert_eq!(
+            over("k7/8/1Q6/8/8/8/8/7K b - - 0 1"),
+            Some(Termination::Stalemate)
+        );
+        assert_eq!(
+            over("k7/8/8/8/8/8/8/6NK w - - 0 1"),
+            Some(Termination::InsufficientMaterial)
+        );
+        assert_eq!(
+            over("kb6/8/8/8/8/8/8/6BK w - - 0 1"),
+            Some(Termination::InsufficientMaterial)
+        );
+        assert_eq!(over("k1b5/8/8/8/8/8/8/6BK w - - 0 1"), None);
+        assert_eq!(over("k7/8/8/8/8/8/8/5NNK w - - 0 1"), None);
+        assert_eq!(
+            over("k7/8/8/8/8/8/8/6RK w - - 100 80"),
+            Some(Termination::FiftyMoves)
+        );
+
+        let mut board = Board::startpos();
+        let mut history = vec![board.hash()];
+        for mv in [
+            "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8",
+        ] {
+            assert_eq!(game_over(&board, &history), None);
+            board.play(mv.parse().unwrap());
+            history.push(board.hash());
+        }
+        assert_eq!(
+            game_over(&board, &history),
+            Some((GameResult::Draw, Termination::Repetition))
+        );
+    }
+
+    #[test]
+    fn adjudication() {
+        let adjudication = Adjudication::default();
+
+        // White's last three moves are all scored as lost, black's in between do not count.
+        let scores = [-1200, 1100, -1300, 0, -1000].map(Some);
+        assert_eq!(
+            adjudication.adjudicate(&scores, 30, Color::White),
+            Some(GameResult::BlackWin)
+        );
+        let scores = [-1200, 1100, -999, 0, -1000].map(Some);
+        assert_eq!(adjudication.adjudicate(&scores, 30, Color::White), None);
+
+        let scores = [5, -3, 0, 10, -10, 2, 0, 1].map(Some);
+        assert_eq!(
+            adjudication.adjudicate(&scores, 40, Color::Black),
+            Some(GameResult::Draw)
+        );
+        assert_eq!(adjudication.adjudicate(&scores, 39, Color::Black), None);
+
+        let mut scores = scores.to_vec();
+        scores[3] = None;
+        assert_eq!(adjudication.adjudicate(&scores, 40, Color::Black), None);
+    }
+}
@@ -0,0 +1,461 @@
+pub mod game;
+pub mod sprt;
+
+use self::{
+    game::{play_game, Adjudication, GameRecord, Opening, TimeControl},
+    sprt::{Decision, Score, Sprt},
+};
+use crate::{
+    chess::{
+        epd::Epd,
+        pgn::{result_str, Game, GameResult, PgnReader, PgnWriter},
+    },
+    tools::rng::Rng,
+    uci::client::Engine,
+};
+use cozy_chess::Board;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+struct EngineConfig {
+    // `self` runs this binary.
+    path: String,
+    name: Option<String>,
+    options: Vec<(String, String)>,
+}
+
+struct MatchOptions {
+    engines: Vec<EngineConfig>,
+    games: usize,
+    concurrency: usize,
+    time_control: TimeControl,
+    openings: Option<String>,
+    // The number of plies taken from each game of a PGN opening book
+    plies: usize,
+    pgn: Option<String>,
+    sprt: Option<Sprt>,
+    adjudication: Option<Adjudication>,
+    seed: Option<u64>,
+}
+
+fn parse_args(args: &[String]) -> Option<MatchOptions> {
+    let mut options = MatchOptions {
+        engines: Vec::new(),
+        games: 100,
+        concurrency: 1,
+        time_control: TimeControl::parse("10+0.1").unwrap(),
+        openings: None,
+        plies: 8,
+        pgn: None,
+        sprt: None,
+        adjudication: Some(Adjudication::default()),
+        seed: None,
+    };
+    let mut adjudication = Adjudication::default();
+    let (mut alpha, mut beta) = (0.05, 0.05);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => options.engines.push(EngineConfig {
+                path: args.next()?.clone(),
+                name: None,
+                options: Vec::new(),
+            }),
+            "--name" => options.engines.last_mut()?.name = Some(args.next()?.clone()),
+            "--option" => {
+                let (name, value) = args.next()?.split_once('=')?;
+                let engine = options.engines.last_mut()?;
+                engine.options.push((name.to_string(), value.to_string()));
+            }
+            "--games" => options.games = args.next()?.parse().ok()?,
+            "--concurrency" => options.concurrency = args.next()?.parse::<usize>().ok()?.max(1),
+            "--tc" => options.time_control = TimeControl::parse(args.next()?)?,
+            "--openings" => options.openings = Some(args.next()?.clone()),
+            "--plies" => options.plies = args.next()?.parse().ok()?,
+            "--pgn" => options.pgn = Some(args.next()?.clone()),
+            "--sprt" => {
+                let elo0 = args.next()?.parse().ok()?;
+                let elo1 = args.next()?.parse().ok()?;
+                options.sprt = Some(Sprt {
+                    elo0,
+                    elo1,
+                    alpha,
+                    beta,
+                });
+            }
+            "--alpha" => alpha = args.next()?.parse().ok()?,
+            "--beta" => beta = args.next()?.parse().ok()?,
+            "--resign-moves" => adjudication.resign_moves = args.next()?.parse().ok()?,
+            "--resign-score" => adjudication.resign_score = args.next()?.parse().ok()?,
+            "--draw-after" => adjudication.draw_after = args.next()?.parse().ok()?,
+            "--draw-moves" => adjudication.draw_moves = args.next()?.parse().ok()?,
+            "--draw-score" => adjudication.draw_score = args.next()?.parse().ok()?,
+            "--no-adjudication" => options.adjudication = None,
+            "--seed" => options.seed = Some(args.next()?.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    if let Some(x) = options.adjudication.as_mut() {
+        *x = adjudication;
+    }
+    if let Some(sprt) = options.sprt.as_mut() {
+        sprt.alpha = alpha;
+        sprt.beta = beta;
+    }
+
+    (options.engines.len() == 2 && options.games > 0).then_some(options)
+}
+
+// Every line of an EPD file, or the first `plies` moves of every game in a PGN file.
+fn load_openings(path: &str, plies: usize) -> Result<Vec<Opening>, String> {
+    if path.ends_with(".pgn") {
+        let file = File::open(path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+        let mut openings = Vec::new();
+
+        for game in PgnReader::new(BufReader::new(file)) {
+            match game {
+                Ok(game) => openings.push(Opening {
+                    start: game.start,
+                    moves: game.moves.into_iter().take(plies).collect(),
+                }),
+                Err(e) => println!("Skipping opening: {e}"),
+            }
+        }
+        return Ok(openings);
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(Epd::parse)
+        .map(|epd| Opening {
+            start: epd.board,
+            moves: Vec::new(),
+        })
+        .collect())
+}
+
+fn start_engine(config: &EngineConfig) -> Result<Engine, String> {
+    let path = match config.path.as_str() {
+        "self" => std::env::current_exe()
+            .map_err(|e| format!("Failed to find the honse binary: {e}"))?
+            .to_string_lossy()
+            .into_owned(),
+        path => path.to_string(),
+    };
+
+    let mut engine = Engine::start(&path, &config.options)
+        .map_err(|e| format!("Failed to start {}: {e}", config.path))?;
+    if let Some(name) = &config.name {
+        engine.name = name.clone();
+    }
+
+    Ok(engine)
+}
+
+struct Finished {
+    index: usize,
+    // Whether the first engine played white
+    first_white: bool,
+    record: GameRecord,
+}
+
+fn worker(
+    options: &MatchOptions,
+    openings: &[Opening],
+    next: &AtomicUsize,
+    stop: &AtomicBool,
+    sender: mpsc::Sender<Result<Finished, String>>,
+) {
+    let start = || -> Result<[Engine; 2], String> {
+        Ok([
+            start_engine(&options.engines[0])?,
+            start_engine(&options.engines[1])?,
+        ])
+    };
+    let mut engines = match start() {
+        Ok(engines) => engines,
+        Err(e) => {
+            let _ = sender.send(Err(e));
+            return;
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        if index >= options.games {
+            return;
+        }
+
+        // Each opening is played twice, with the engines swapping colors.
+        let opening = &openings[index / 2 % openings.len()];
+        let first_white = index.is_multiple_of(2);
+        let [first, second] = &mut engines;
+        let players = if first_white {
+            [first, second]
+        } else {
+            [second, first]
+        };
+
+        let record = play_game(
+            players,
+            opening,
+            options.time_control,
+            options.adjudication.as_ref(),
+        );
+
+        let restart = record.termination.needs_restart();
+        let finished = Finished {
+            index,
+            first_white,
+            record,
+        };
+        if sender.send(Ok(finished)).is_err() {
+            return;
+        }
+
+        if restart {
+            engines = match start() {
+                Ok(engines) => engines,
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            };
+        }
+    }
+}
+
+fn to_pgn(finished: &Finished, names: &[String; 2], opening: &Opening, tc: TimeControl) -> Game {
+    let (white, black) = if finished.first_white {
+        (&names[0], &names[1])
+    } else {
+        (&names[1], &names[0])
+    };
+    let tags = [
+        ("Event", "honse match".to_string()),
+        ("Round", (finished.index + 1).to_string()),
+        ("White", white.clone()),
+        ("Black", black.clone()),
+        ("TimeControl", tc.to_string()),
+        ("Termination", finished.record.termination.tag().to_string()),
+    ];
+
+    Game {
+        tags: tags
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        start: opening.start.clone(),
+        moves: opening
+            .moves
+            .iter()
+            .chain(&finished.record.moves)
+            .copied()
+            .collect(),
+        result: Some(finished.record.result),
+    }
+}
+
+pub fn matches(args: &[String]) {
+    let Some(options) = parse_args(args) else {
+        println!("Usage: honse match --engine <path|self> [--name <name>] [--option <name>=<value>]... --engine <path|self> [...] [--games <n>] [--concurrency <n>] [--tc <seconds>+<increment>] [--openings <epd|pgn>] [--plies <n>] [--pgn <output>] [--sprt <elo0> <elo1>] [--alpha <a>] [--beta <b>] [--resign-moves <n>] [--resign-score <cp>] [--draw-after <move>] [--draw-moves <n>] [--draw-score <cp>] [--no-adjudication] [--seed <n>]");
+        return;
+    };
+
+    let mut openings = match &options.openings {
+        Some(path) => match load_openings(path, options.plies) {
+            Ok(openings) => openings,
+            Err(e) => {
+                println!("{e}");
+                return;
+            }
+        },
+        None => Vec::new(),
+    };
+    if openings.is_empty() {
+        openings.push(Opening {
+            start: Board::startpos(),
+            moves: Vec::new(),
+        });
+    }
+    if let Some(seed) = options.seed {
+        let mut rng = Rng::new(seed);
+        for i in (1..openings.len()).rev() {
+            openings.swap(i, rng.below(i + 1));
+        }
+    }
+
+    // Start the engines once up front to learn their names.
+    let mut names = match (
+        start_engine(&options.engines[0]),
+        start_engine(&options.engines[1]),
+    ) {
+        (Ok(first), Ok(second)) => [first.name.clone(), second.name.clone()],
+        (Err(e), _) | (_, Err(e)) => {
+            println!("{e}");
+            return;
+        }
+    };
+    if names[0] == names[1] {
+        names[0] += " (1)";
+        names[1] += " (2)";
+    }
+
+    let mut pgn = match &options.pgn {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(PgnWriter::new(BufWriter::new(file))),
+            Err(e) => {
+                println!("Failed to open {path}: {e}");
+                return;
+            }
+        },
+        None => None,
+    };
+
+    println!(
+        "Playing {} games of {} vs {} at {} with {} concurrent games",
+        options.games,
+        names[0],
+        names[1],
+        options.time_control,
+        options.concurrency.min(options.games)
+    );
+
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    let mut score = Score::default();
+    let mut decision = None;
+
+    thread::scope(|s| {
+        for _ in 0..options.concurrency.min(options.games) {
+            let sender = sender.clone();
+            let (options, openings, next, stop) = (&options, &openings, &next, &stop);
+            s.spawn(move || worker(options, openings, next, stop, sender));
+        }
+        drop(sender);
+
+        for finished in receiver {
+            let finished = match finished {
+                Ok(finished) => finished,
+                Err(e) => {
+                    println!("{e}");
+                    stop.store(true, Ordering::Relaxed);
+                    continue;
+                }
+            };
+
+            let record = &finished.record;
+            let first_won = match record.result {
+                GameResult::WhiteWin => Some(finished.first_white),
+                GameResult::BlackWin => Some(!finished.first_white),
+                GameResult::Draw => None,
+            };
+            match first_won {
+                Some(true) => score.wins += 1,
+                Some(false) => score.losses += 1,
+                None => score.draws += 1,
+            }
+
+            let (white, black) = if finished.first_white {
+                (&names[0], &names[1])
+            } else {
+                (&names[1], &names[0])
+            };
+            println!(
+                "Finished game {} ({white} vs {black}): {} {{{}}}",
+                finished.index + 1,
+                result_str(Some(record.result)),
+                record.termination
+            );
+            println!(
+                "Score of {} vs {}: {} - {} - {}  [{:.3}] {}",
+                names[0],
+                names[1],
+                score.wins,
+                score.losses,
+                score.draws,
+                score.mean(),
+                score.games()
+            );
+            if let Some((elo, error)) = score.elo() {
+                println!("Elo difference: {elo:.1} +/- {error:.1}");
+            }
+
+            if let Some(sprt) = &options.sprt {
+                let (lower, upper) = sprt.bounds();
+                println!(
+                    "SPRT: llr {:.2} ({:.2}, {:.2}) [{}, {}]",
+                    sprt.llr(&score),
+                    lower,
+                    upper,
+                    sprt.elo0,
+                    sprt.elo1
+                );
+
+                if decision.is_none() {
+                    decision = sprt.decision(&score);
+                    if decision.is_some() {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            if let Some(writer) = pgn.as_mut() {
+                let opening = &openings[finished.index / 2 % openings.len()];
+                let game = to_pgn(&finished, &names, opening, options.time_control);
+                if let Err(e) = writer.write_game(&game).and_then(|_| writer.flush()) {
+                    println!("Failed to write PGN: {e}");
+                }
+            }
+        }
+    });
+
+    match decision {
+        Some(Decision::AcceptH1) => println!("SPRT: H1 accepted"),
+        Some(Decision::AcceptH0) => println!("SPRT: H0 accepted"),
+        None if options.sprt.is_some() => println!("SPRT: no decision"),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_ascii_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn arguments() {
+        let options = parse_args(&args(
+            "--engine self --option Threads=1 --engine ./old --name Old --tc 5+0.05 --alpha 0.1 --sprt 0 5 --no-adjudication",
+        ))
+        .unwrap();
+        assert_eq!(
+            options.engines[0].options,
+            [("Threads".to_string(), "1".to_string())]
+        );
+        assert_eq!(options.engines[1].name.as_deref(), Some("Old"));
+        assert_eq!(options.time_control, TimeControl::parse("5+0.05").unwrap());
+        let sprt = options.sprt.unwrap();
+        assert_eq!((sprt.elo1, sprt.alpha, sprt.beta), (5.0, 0.1, 0.05));
+        assert!(options.adjudication.is_none());
+
+        assert!(parse_args(&args("--engine self")).is_none());
+        assert!(parse_args(&args("--name x --engine a --engine b")).is_none());
+        assert!(parse_args(&args("--engine a --engine b --option x")).is_none());
+    }
+}
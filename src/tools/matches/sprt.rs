@@ -0,0 +1,176 @@
+// Wins, draws and losses from the point of view of the first engine.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // The average points per game, in 0..=1
+    pub fn mean(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    // The variance of the points scored in a single game.
+    fn variance(&self) -> f64 {
+        let n = self.games().max(1) as f64;
+        let mean = self.mean();
+
+        (self.wins as f64 * (1.0 - mean).powi(2)
+            + self.draws as f64 * (0.5 - mean).powi(2)
+            + self.losses as f64 * mean.powi(2))
+            / n
+    }
+
+    // The Elo difference and the half width of its 95% confidence interval. None
+    // until both engines have scored and lost a point, as the difference is infinite.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let mean = self.mean();
+        if self.games() == 0 || mean <= 0.0 || mean >= 1.0 {
+            return None;
+        }
+
+        let deviation = (self.variance() / self.games() as f64).sqrt();
+        let bound = |score: f64| score_to_elo(score.clamp(1e-6, 1.0 - 1e-6));
+        let error = (bound(mean + 1.96 * deviation) - bound(mean - 1.96 * deviation)) / 2.0;
+
+        Some((score_to_elo(mean), error))
+    }
+}
+
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn score_to_elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+// Tests H0: elo = elo0 against H1: elo = elo1, with logistic Elo.
+#[derive(Clone, Copy, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Decision {
+    AcceptH0,
+    AcceptH1,
+}
+
+impl Sprt {
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    // The generalised SPRT log-likelihood ratio, using the normal approximation
+    // of the trinomial distribution of game results.
+    pub fn llr(&self, score: &Score) -> f64 {
+        let variance = score.variance();
+        if score.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+
+        let (s0, s1) = (elo_to_score(self.elo0), elo_to_score(self.elo1));
+        score.games() as f64 * (s1 - s0) * (2.0 * score.mean() - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn decision(&self, score: &Score) -> Option<Decision> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            Some(Decision::AcceptH1)
+        } else if llr <= lower {
+            Some(Decision::AcceptH0)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn elo() {
+        let score = Score {
+            wins: 60,
+            draws: 30,
+            losses: 10,
+        };
+        let (elo, error) = score.elo().unwrap();
+        assert!(close(elo, 190.85), "{elo}");
+        assert!(error > 50.0 && error < 100.0, "{error}");
+
+        let even = Score {
+            wins: 10,
+            draws: 10,
+            losses: 10,
+        };
+        assert!(close(even.elo().unwrap().0, 0.0));
+        assert!(close(score_to_elo(elo_to_score(35.0)), 35.0));
+
+        assert_eq!(Score::default().elo(), None);
+        assert_eq!(
+            Score {
+                wins: 3,
+                ..Default::default()
+            }
+            .elo(),
+            None
+        );
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        let (lower, upper) = sprt.bounds();
+        assert!(close(lower, -2.944) && close(upper, 2.944));
+
+        let score = Score {
+            wins: 60,
+            draws: 30,
+            losses: 10,
+        };
+        assert!(close(sprt.llr(&score), 3.105), "{}", sprt.llr(&score));
+        assert_eq!(sprt.decision(&score), Some(Decision::AcceptH1));
+
+        let reversed = Score {
+            wins: 10,
+            draws: 30,
+            losses: 60,
+        };
+        assert_eq!(sprt.decision(&reversed), Some(Decision::AcceptH0));
+
+        let even = Score {
+            wins: 10,
+            draws: 10,
+            losses: 10,
+        };
+        assert!(sprt.llr(&even) < 0.0);
+        assert_eq!(sprt.decision(&even), None);
+        assert_eq!(sprt.llr(&Score::default()), 0.0);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod cli;
+pub mod data;
+pub mod datagen;
+pub mod epd;
+pub mod makebook;
+pub mod matches;
+pub mod perftdiff;
+pub mod perftsuite;
+pub mod rng;
+pub mod symmetry;
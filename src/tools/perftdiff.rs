@@ -0,0 +1,263 @@
+use crate::{
+    chess::{parse_move::move_to_string, perft::PerftOptions},
+    uci::client::Engine,
+};
+use cozy_chess::{Board, Move};
+use std::{collections::HashMap, io, time::Duration};
+
+// The reference engine must understand `go perft <depth>` and print its divide as
+// `<move>: <nodes>` lines followed by `Nodes searched: <total>`, as Stockfish does.
+fn reference_divide(
+    engine: &mut Engine,
+    fen: &str,
+    moves: &[String],
+    depth: u8,
+) -> io::Result<Vec<(String, u64)>> {
+    engine.position(fen, moves)?;
+    engine.send(&format!("go perft {depth}"))?;
+
+    let (lines, _) = engine.read_until("Nodes searched", Duration::MAX)?;
+    Ok(lines
+        .iter()
+        .filter_map(|line| parse_divide_line(line))
+        .collect())
+}
+
+fn parse_divide_line(line: &str) -> Option<(String, u64)> {
+    let (mv, nodes) = line.split_once(':')?;
+    let mv = mv.trim();
+    if mv.parse::<Move>().is_err() {
+        return None;
+    }
+
+    Some((mv.to_string(), nodes.trim().parse().ok()?))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Difference {
+    // Generated only by the reference engine
+    Missing(String),
+    // Generated only by us
+    Extra(String),
+    // Move, our count, the reference count
+    Count(String, u64, u64),
+}
+
+fn compare(ours: &[(String, u64)], theirs: &[(String, u64)]) -> Vec<Difference> {
+    let reference: HashMap<&str, u64> = theirs.iter().map(|(mv, n)| (mv.as_str(), *n)).collect();
+    let mut differences = Vec::new();
+
+    for (mv, nodes) in ours {
+        match reference.get(mv.as_str()) {
+            None => differences.push(Difference::Extra(mv.clone())),
+            Some(&expected) if expected != *nodes => {
+                differences.push(Difference::Count(mv.clone(), *nodes, expected))
+            }
+            _ => {}
+        }
+    }
+
+    for (mv, _) in theirs {
+        if !ours.iter().any(|(ours, _)| ours == mv) {
+            differences.push(Difference::Missing(mv.clone()));
+        }
+    }
+
+    differences
+}
+
+struct Options {
+    engine: String,
+    depth: u8,
+    fen: String,
+    moves: Vec<String>,
+    frc: bool,
+    perft: PerftOptions,
+}
+
+fn parse_args(args: &[String]) -> Option<Options> {
+    let mut options = Options {
+        engine: String::new(),
+        depth: 0,
+        fen: Board::startpos().to_string(),
+        moves: Vec::new(),
+        frc: false,
+        perft: PerftOptions::new(),
+    };
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fen" => options.fen = args.next()?.clone(),
+            "--moves" => {
+                options.moves = args
+                    .next()?
+                    .split_ascii_whitespace()
+                    .map(String::from)
+                    .collect()
+            }
+            "--frc" => options.frc = true,
+            "--threads" => options.perft.threads = args.next()?.parse::<usize>().ok()?.max(1),
+            "--hash" => options.perft.set_hash(args.next()?.parse().ok()?),
+            _ if arg.starts_with("--") => return None,
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match positional.as_slice() {
+        [engine, depth] => {
+            options.engine = engine.clone();
+            options.depth = depth.parse().ok().filter(|&depth| depth > 0)?;
+        }
+        _ => return None,
+    }
+
+    Some(options)
+}
+
+fn name(board: &Board, mv: Move, frc: bool) -> String {
+    if frc {
+        mv.to_string()
+    } else {
+        move_to_string(board, mv)
+    }
+}
+
+fn play(board: &mut Board, mv: &str, frc: bool) -> bool {
+    let mv = if frc {
+        mv.parse().ok()
+    } else {
+        crate::chess::parse_move::parse_move(board, mv).ok()
+    };
+
+    match mv {
+        Some(mv) => board.try_play(mv).is_ok(),
+        None => false,
+    }
+}
+
+// Walks down the tree, following the first move whose count differs from the
+// reference engine, until the divides disagree on which moves are legal.
+// Returns false if a difference is found.
+pub fn perftdiff(args: &[String]) -> bool {
+    let options = match parse_args(args) {
+        Some(options) => options,
+        None => {
+            println!(
+                "Usage: honse perftdiff <engine> <depth> [--fen <fen>] [--moves \"<uci moves>\"] [--frc] [--threads <n>] [--hash <mb>]"
+            );
+            return false;
+        }
+    };
+
+    let mut board = match Board::from_fen(&options.fen, options.frc) {
+        Ok(board) => board,
+        Err(e) => {
+            println!("Invalid FEN {}: {e:?}", options.fen);
+            return false;
+        }
+    };
+    for mv in &options.moves {
+        if !play(&mut board, mv, options.frc) {
+            println!("Illegal move {mv}");
+            return false;
+        }
+    }
+
+    let chess960 = [("UCI_Chess960".to_string(), "true".to_string())];
+    let engine_options: &[_] = if options.frc { &chess960 } else { &[] };
+    let mut engine = match Engine::start(&options.engine, engine_options) {
+        Ok(engine) => engine,
+        Err(e) => {
+            println!("Failed to start {}: {e}", options.engine);
+            return false;
+        }
+    };
+
+    let mut moves = options.moves.clone();
+    for depth in (1..=options.depth).rev() {
+        let ours: Vec<(String, u64)> = options
+            .perft
+            .divide(&board, depth)
+            .into_iter()
+            .map(|(mv, nodes)| (name(&board, mv, options.frc), nodes))
+            .collect();
+        let theirs = match reference_divide(&mut engine, &options.fen, &moves, depth) {
+            Ok(theirs) => theirs,
+            Err(e) => {
+                println!("Reference engine error: {e}");
+                return false;
+            }
+        };
+
+        let differences = compare(&ours, &theirs);
+        let illegal = differences
+            .iter()
+            .any(|difference| !matches!(difference, Difference::Count(..)));
+
+        if differences.is_empty() {
+            if depth == options.depth {
+                println!("No differences at depth {depth}");
+                return true;
+            }
+            // Only possible if the reference is inconsistent with its own parent divide
+            println!("Divides agree at depth {depth} but their parent did not");
+            break;
+        }
+
+        if illegal {
+            if options.frc {
+                println!("Position: {board:#}");
+            } else {
+                println!("Position: {board}");
+            }
+            println!("Moves: {}", moves.join(" "));
+            for difference in &differences {
+                match difference {
+                    Difference::Missing(mv) => println!("  {mv} generated only by the reference"),
+                    Difference::Extra(mv) => println!("  {mv} generated only by honse"),
+                    Difference::Count(mv, ours, theirs) => {
+                        println!("  {mv} {ours} nodes, reference {theirs}")
+                    }
+                }
+            }
+            break;
+        }
+
+        // Every difference is a count, so follow the first one down.
+        if let Difference::Count(mv, ours, theirs) = &differences[0] {
+            println!("depth {depth}: {mv} {ours} nodes, reference {theirs}");
+            play(&mut board, mv, options.frc);
+            moves.push(mv.clone());
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences() {
+        let line = |mv: &str, n: u64| (mv.to_string(), n);
+        let ours = [line("e2e4", 20), line("d2d4", 21), line("e1g1", 1)];
+        let theirs = [line("e2e4", 20), line("d2d4", 20), line("a2a3", 1)];
+
+        assert_eq!(
+            compare(&ours, &theirs),
+            [
+                Difference::Count("d2d4".to_string(), 21, 20),
+                Difference::Extra("e1g1".to_string()),
+                Difference::Missing("a2a3".to_string()),
+            ]
+        );
+        assert!(compare(&ours, &ours).is_empty());
+
+        assert_eq!(parse_divide_line("a7a8q: 12"), Some(line("a7a8q", 12)));
+        assert_eq!(parse_divide_line("Nodes searched: 20"), None);
+        assert_eq!(parse_divide_line("info string hi"), None);
+    }
+}
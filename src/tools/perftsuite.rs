@@ -0,0 +1,180 @@
+use crate::chess::{parse_move::move_to_string, perft::PerftOptions};
+use cozy_chess::Board;
+use std::{fs, time::Instant};
+
+struct PerftCase {
+    fen: String,
+    board: Board,
+    frc: bool,
+    // Depth and expected node count
+    depths: Vec<(u8, u64)>,
+}
+
+// Parses `<fen> ;D1 20 ;D2 400 ...`, with either standard or Shredder castling rights.
+fn parse_line(line: &str) -> Result<PerftCase, String> {
+    let mut parts = line.split(';');
+    let fen = parts.next().unwrap_or_default().trim().to_string();
+
+    let (board, frc) = match Board::from_fen(&fen, false) {
+        Ok(board) => (board, false),
+        Err(_) => match Board::from_fen(&fen, true) {
+            Ok(board) => (board, true),
+            Err(e) => return Err(format!("invalid FEN {fen}: {e:?}")),
+        },
+    };
+
+    let mut depths = Vec::new();
+    for part in parts {
+        let part = part.trim();
+        let depth = part
+            .strip_prefix('D')
+            .and_then(|part| part.split_once(char::is_whitespace));
+        match depth.map(|(d, n)| (d.parse(), n.trim().parse())) {
+            Some((Ok(depth), Ok(nodes))) => depths.push((depth, nodes)),
+            _ => return Err(format!("invalid depth {part}")),
+        }
+    }
+
+    Ok(PerftCase {
+        fen,
+        board,
+        frc,
+        depths,
+    })
+}
+
+fn divide(case: &PerftCase, depth: u8, options: &PerftOptions) {
+    println!("\nDivide of {} at depth {depth}:", case.fen);
+
+    for (mv, nodes) in options.divide(&case.board, depth) {
+        let name = if case.frc {
+            mv.to_string()
+        } else {
+            move_to_string(&case.board, mv)
+        };
+        println!("{name} {nodes}");
+    }
+}
+
+// Returns false if any position fails or cannot be parsed.
+pub fn perftsuite(args: &[String]) -> bool {
+    let mut path = None;
+    let mut max_depth = u8::MAX;
+    let mut options = PerftOptions::new();
+    let mut valid = true;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().and_then(|value| value.parse::<usize>().ok());
+        match arg.as_str() {
+            "--max-depth" => match value() {
+                Some(depth) => max_depth = depth.min(u8::MAX as usize) as u8,
+                None => valid = false,
+            },
+            "--threads" => match value() {
+                Some(threads) => options.threads = threads.max(1),
+                None => valid = false,
+            },
+            "--hash" => match value() {
+                Some(mb) => options.set_hash(mb),
+                None => valid = false,
+            },
+            arg => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path.filter(|_| valid) else {
+        println!(
+            "Usage: honse perftsuite <file.epd> [--max-depth <n>] [--threads <n>] [--hash <mb>]"
+        );
+        return false;
+    };
+
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            println!("Failed to read {path}: {e}");
+            return false;
+        }
+    };
+
+    let start = Instant::now();
+    let (mut positions, mut failures, mut total) = (0, 0, 0);
+    let mut first_failure = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        positions += 1;
+
+        let case = match parse_line(line) {
+            Ok(case) => case,
+            Err(e) => {
+                println!("line {}: {e}", number + 1);
+                failures += 1;
+                continue;
+            }
+        };
+
+        let mut failed = false;
+        for &(depth, expected) in case.depths.iter().filter(|&&(d, _)| d <= max_depth) {
+            let nodes = options.perft(&case.board, depth);
+            total += nodes;
+
+            if nodes != expected {
+                println!(
+                    "FAIL {} depth {depth}: expected {expected}, got {nodes}",
+                    case.fen
+                );
+                failed = true;
+                if first_failure.is_none() {
+                    first_failure = Some((depth, case));
+                }
+                break;
+            }
+        }
+
+        if failed {
+            failures += 1;
+        } else {
+            println!("ok   {}", line.split(';').next().unwrap_or_default().trim());
+        }
+    }
+
+    if let Some((depth, case)) = first_failure {
+        divide(&case, depth, &options);
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "\nPassed {}/{positions} positions, {total} nodes in {} ms ({} nps)",
+        positions - failures,
+        elapsed.as_millis(),
+        (total as f64 / elapsed.as_secs_f64()) as u64
+    );
+
+    failures == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let case = parse_line("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 ;D1 26 ;D2 568").unwrap();
+        assert_eq!(case.depths, [(1, 26), (2, 568)]);
+        assert!(!case.frc);
+
+        let case =
+            parse_line("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9 ;D1 21")
+                .unwrap();
+        assert!(case.frc);
+        assert_eq!(PerftOptions::new().perft(&case.board, 1), 21);
+
+        assert!(parse_line("not a fen ;D1 20").is_err());
+        assert!(parse_line("8/8/8/8/8/8/8/K1k5 w - - 0 1 ;D1 x").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    chess::{
+        epd::Epd,
+        mirror::{flip_colors, flip_files},
+    },
+    search::{
+        eval::{eval, eval_trace},
+        evaluation::trace::{EvalTrace, Term},
+        pawn_table::PawnTable,
+    },
+    uci::bench::FENS,
+};
+use cozy_chess::{Board, Color};
+use std::fs;
+
+#[derive(Debug)]
+pub struct Asymmetry {
+    pub fen: String,
+    pub mirror: &'static str,
+    pub term: &'static str,
+}
+
+fn compare(
+    fen: &str,
+    mirror: &'static str,
+    original: &EvalTrace,
+    mirrored: &EvalTrace,
+    swap_colors: bool,
+) -> Vec<Asymmetry> {
+    let mut asymmetries = Vec::new();
+
+    for term in Term::ALL {
+        if !swap_colors && !term.is_file_symmetric() {
+            continue;
+        }
+
+        let symmetric = Color::ALL.iter().all(|&color| {
+            let other = if swap_colors { !color } else { color };
+            original.term(term, color) == mirrored.term(term, other)
+        });
+
+        if !symmetric {
+            asymmetries.push(Asymmetry {
+                fen: fen.to_string(),
+                mirror,
+                term: term.name(),
+            });
+        }
+    }
+
+    asymmetries
+}
+
+// Checks that mirroring the position changes the evaluation exactly as expected:
+// a color flip negates it, and a horizontal mirror leaves every file-symmetric term as is.
+pub fn check(board: &Board) -> Vec<Asymmetry> {
+    let fen = board.to_string();
+    let mut pawn_table = PawnTable::new(1);
+    let original = eval_trace(board);
+
+    let flipped = flip_colors(board);
+    let mut asymmetries = compare(&fen, "vertical", &original, &eval_trace(&flipped), true);
+    if eval(board, &mut pawn_table) != eval(&flipped, &mut pawn_table) {
+        asymmetries.push(Asymmetry {
+            fen: fen.clone(),
+            mirror: "vertical",
+            term: "Final evaluation",
+        });
+    }
+
+    if let Some(mirrored) = flip_files(board) {
+        asymmetries.extend(compare(
+            &fen,
+            "horizontal",
+            &original,
+            &eval_trace(&mirrored),
+            false,
+        ));
+    }
+
+    asymmetries
+}
+
+pub fn symmetry(path: Option<&str>) -> bool {
+    let mut boards: Vec<Board> = FENS.iter().map(|fen| fen.parse().unwrap()).collect();
+
+    if let Some(path) = path {
+        match fs::read_to_string(path) {
+            Ok(contents) => boards.extend(
+                contents
+                    .lines()
+                    .filter_map(|line| Epd::parse(line).map(|epd| epd.board)),
+            ),
+            Err(e) => {
+                println!("Failed to read {path}: {e}");
+                return false;
+            }
+        }
+    }
+
+    let asymmetries: Vec<Asymmetry> = boards.iter().flat_map(check).collect();
+
+    for asymmetry in &asymmetries {
+        println!(
+            "{} asymmetry in {}: {}",
+            asymmetry.mirror, asymmetry.term, asymmetry.fen
+        );
+    }
+    println!(
+        "Symmetry: {} positions, {} asymmetries",
+        boards.len(),
+        asymmetries.len()
+    );
+
+    asymmetries.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_positions() {
+        for fen in FENS {
+            let asymmetries = check(&fen.parse().unwrap());
+            assert!(asymmetries.is_empty(), "{asymmetries:?}");
+        }
+    }
+
+    #[test]
+    fn epd() {
+        let board = Epd::parse("8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - bm Kg3;")
+            .unwrap()
+            .board;
+
+        assert_eq!(
+            board,
+            "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - 0 1"
+                .parse()
+                .unwrap()
+        );
+        assert!(check(&board).is_empty());
+    }
+}
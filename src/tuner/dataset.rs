@@ -0,0 +1,112 @@
+use super::weights::NUM_PARAMS;
+use crate::{
+    search::{eval::traced, evaluation::trace::Term, evaluation::trace::Trace},
+    tools::data::text::parse_line,
+};
+use cozy_chess::{Board, Color};
+use std::{fs, io, thread};
+
+pub struct Entry {
+    // Non-zero (parameter, white count - black count) pairs.
+    pub coeffs: Vec<(u16, i16)>,
+    // Middlegame weight out of 24.
+    pub phase: u8,
+    // From white's point of view.
+    pub result: f32,
+    pub score: Option<f32>,
+}
+
+struct CoeffTrace {
+    counts: Vec<i32>,
+    phase: i32,
+}
+
+impl Trace for CoeffTrace {
+    fn add(&mut self, _: Term, _: Color, _: i32, _: i32) {}
+
+    fn set_phase(&mut self, phase: i32) {
+        self.phase = phase;
+    }
+
+    fn coeff(&mut self, param: usize, color: Color, count: i32) {
+        match color {
+            Color::White => self.counts[param] += count,
+            Color::Black => self.counts[param] -= count,
+        }
+    }
+}
+
+pub fn entry(board: &Board, result: f32, score: Option<f32>) -> Entry {
+    let mut trace = CoeffTrace {
+        counts: vec![0; NUM_PARAMS],
+        phase: 0,
+    };
+    traced(board, &mut trace);
+
+    Entry {
+        coeffs: trace
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count != 0)
+            .map(|(param, &count)| (param as u16, count as i16))
+            .collect(),
+        phase: trace.phase as u8,
+        result,
+        score,
+    }
+}
+
+pub fn load(path: &str, threads: usize) -> io::Result<Vec<Entry>> {
+    let contents = fs::read_to_string(path)?;
+    let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+    let chunk = lines.len().div_ceil(threads).max(1);
+
+    let entries: Vec<Vec<Entry>> = thread::scope(|s| {
+        let handles: Vec<_> = lines
+            .chunks(chunk)
+            .map(|lines| {
+                s.spawn(move || {
+                    lines
+                        .iter()
+                        .filter_map(|line| parse_line(line))
+                        .map(|(board, result, score)| entry(&board, result, score))
+                        .collect()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let entries: Vec<Entry> = entries.into_iter().flatten().collect();
+    if entries.len() < lines.len() {
+        println!("Skipped {} unparsable lines", lines.len() - entries.len());
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search::eval::eval_trace, tuner::weights::current, uci::bench::FENS};
+
+    #[test]
+    fn coefficients_match_eval() {
+        let weights = current();
+
+        for fen in FENS {
+            let board: Board = fen.parse().unwrap();
+            let entry = entry(&board, 0.5, None);
+
+            let (mg, eg) = entry.coeffs.iter().fold((0.0, 0.0), |(mg, eg), &(i, c)| {
+                let w = weights[i as usize];
+                (mg + w[0] * c as f64, eg + w[1] * c as f64)
+            });
+            let phase = entry.phase as f64;
+            let score = (mg * phase + eg * (24.0 - phase)) / 24.0;
+
+            assert_eq!(score as i32, eval_trace(&board).score);
+        }
+    }
+}
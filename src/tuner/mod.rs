@@ -0,0 +1,90 @@
+mod dataset;
+mod optimizer;
+mod weights;
+
+use optimizer::Adam;
+use std::{fs, thread, time::Instant};
+
+struct TuneOptions {
+    dataset: String,
+    output: String,
+    epochs: usize,
+    learning_rate: f64,
+    lambda: f64,
+    threads: usize,
+}
+
+fn parse_args(args: &[String]) -> Option<TuneOptions> {
+    let mut options = TuneOptions {
+        dataset: String::new(),
+        output: String::from("psts_tuned.rs"),
+        epochs: 1000,
+        learning_rate: 1.0,
+        lambda: 1.0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => options.output = args.next()?.clone(),
+            "--epochs" => options.epochs = args.next()?.parse().ok()?,
+            "--lr" => options.learning_rate = args.next()?.parse().ok()?,
+            "--lambda" => options.lambda = args.next()?.parse().ok()?,
+            "--threads" => options.threads = args.next()?.parse::<usize>().ok()?.max(1),
+            path => options.dataset = path.to_string(),
+        }
+    }
+
+    (!options.dataset.is_empty()).then_some(options)
+}
+
+pub fn tune(args: &[String]) {
+    let Some(options) = parse_args(args) else {
+        println!(
+            "Usage: honse tune <dataset> [--output <file>] [--epochs <n>] [--lr <rate>] [--lambda <result weight>] [--threads <n>]"
+        );
+        return;
+    };
+
+    let start = Instant::now();
+    let entries = match dataset::load(&options.dataset, options.threads) {
+        Ok(entries) if !entries.is_empty() => entries,
+        Ok(_) => {
+            println!("No positions in {}", options.dataset);
+            return;
+        }
+        Err(e) => {
+            println!("Failed to read {}: {e}", options.dataset);
+            return;
+        }
+    };
+    println!(
+        "Loaded {} positions in {} ms",
+        entries.len(),
+        start.elapsed().as_millis()
+    );
+
+    let mut weights = weights::current();
+    let (lambda, threads) = (options.lambda, options.threads);
+    let k = optimizer::fit_k(&entries, &weights, lambda, threads);
+    println!("K = {k:.4}");
+
+    let mut adam = Adam::new(weights.len(), options.learning_rate);
+    for epoch in 1..=options.epochs {
+        let gradient = optimizer::gradient(&entries, &weights, k, lambda, threads);
+        adam.step(&mut weights, &gradient);
+
+        if epoch % 50 == 0 || epoch == options.epochs {
+            let error = optimizer::error(&entries, &weights, k, lambda, threads);
+            println!("Epoch {epoch} error {error:.8}");
+
+            if let Err(e) = fs::write(&options.output, weights::render(&weights)) {
+                println!("Failed to write {}: {e}", options.output);
+                return;
+            }
+        }
+    }
+
+    println!("Wrote {}", options.output);
+}
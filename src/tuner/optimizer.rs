@@ -0,0 +1,182 @@
+use super::{dataset::Entry, weights::Weights};
+use std::thread;
+
+fn sigmoid(k: f64, score: f64) -> f64 {
+    1.0 / (1.0 + (-k * score / 400.0).exp())
+}
+
+fn evaluate(entry: &Entry, weights: &Weights) -> f64 {
+    let (mg, eg) = entry.coeffs.iter().fold((0.0, 0.0), |(mg, eg), &(i, c)| {
+        let w = weights[i as usize];
+        (mg + w[0] * c as f64, eg + w[1] * c as f64)
+    });
+    let phase = entry.phase as f64;
+
+    (mg * phase + eg * (24.0 - phase)) / 24.0
+}
+
+// Blends the game result with the recorded search score, if there is one.
+fn target(entry: &Entry, k: f64, lambda: f64) -> f64 {
+    match entry.score {
+        Some(score) => lambda * entry.result as f64 + (1.0 - lambda) * sigmoid(k, score as f64),
+        None => entry.result as f64,
+    }
+}
+
+// Runs `f` over roughly equal chunks of the entries on every thread and returns the results.
+fn parallel<T: Send>(
+    entries: &[Entry],
+    threads: usize,
+    f: impl Fn(&[Entry]) -> T + Sync,
+) -> Vec<T> {
+    let chunk = entries.len().div_ceil(threads).max(1);
+
+    thread::scope(|s| {
+        let handles: Vec<_> = entries
+            .chunks(chunk)
+            .map(|entries| s.spawn(|| f(entries)))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+}
+
+pub fn error(entries: &[Entry], weights: &Weights, k: f64, lambda: f64, threads: usize) -> f64 {
+    let total: f64 = parallel(entries, threads, |entries| {
+        entries
+            .iter()
+            .map(|e| (sigmoid(k, evaluate(e, weights)) - target(e, k, lambda)).powi(2))
+            .sum::<f64>()
+    })
+    .into_iter()
+    .sum();
+
+    total / entries.len() as f64
+}
+
+// Finds the scaling constant that best maps the current evaluation onto the results.
+pub fn fit_k(entries: &[Entry], weights: &Weights, lambda: f64, threads: usize) -> f64 {
+    let (mut low, mut high) = (0.01, 10.0);
+
+    for _ in 0..50 {
+        let a = low + (high - low) / 3.0;
+        let b = high - (high - low) / 3.0;
+
+        if error(entries, weights, a, lambda, threads) < error(entries, weights, b, lambda, threads)
+        {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+pub fn gradient(
+    entries: &[Entry],
+    weights: &Weights,
+    k: f64,
+    lambda: f64,
+    threads: usize,
+) -> Weights {
+    let partials = parallel(entries, threads, |entries| {
+        let mut gradient = vec![[0.0; 2]; weights.len()];
+
+        for entry in entries {
+            let s = sigmoid(k, evaluate(entry, weights));
+            let delta = (s - target(entry, k, lambda)) * s * (1.0 - s);
+            let mg = delta * entry.phase as f64 / 24.0;
+            let eg = delta * (24.0 - entry.phase as f64) / 24.0;
+
+            for &(i, c) in &entry.coeffs {
+                gradient[i as usize][0] += mg * c as f64;
+                gradient[i as usize][1] += eg * c as f64;
+            }
+        }
+
+        gradient
+    });
+
+    let scale = 2.0 * k / 400.0 / entries.len() as f64;
+    let mut gradient = vec![[0.0; 2]; weights.len()];
+    for partial in partials {
+        for (total, g) in gradient.iter_mut().zip(partial) {
+            total[0] += g[0] * scale;
+            total[1] += g[1] * scale;
+        }
+    }
+
+    gradient
+}
+
+pub struct Adam {
+    m: Weights,
+    v: Weights,
+    t: i32,
+    learning_rate: f64,
+}
+
+impl Adam {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    pub fn new(params: usize, learning_rate: f64) -> Self {
+        Adam {
+            m: vec![[0.0; 2]; params],
+            v: vec![[0.0; 2]; params],
+            t: 0,
+            learning_rate,
+        }
+    }
+
+    pub fn step(&mut self, weights: &mut Weights, gradient: &Weights) {
+        self.t += 1;
+        let m_correction = 1.0 - Self::BETA1.powi(self.t);
+        let v_correction = 1.0 - Self::BETA2.powi(self.t);
+
+        for i in 0..weights.len() {
+            for phase in 0..2 {
+                let g = gradient[i][phase];
+                let m = &mut self.m[i][phase];
+                let v = &mut self.v[i][phase];
+
+                *m = Self::BETA1 * *m + (1.0 - Self::BETA1) * g;
+                *v = Self::BETA2 * *v + (1.0 - Self::BETA2) * g * g;
+
+                let m_hat = *m / m_correction;
+                let v_hat = *v / v_correction;
+                weights[i][phase] -= self.learning_rate * m_hat / (v_hat.sqrt() + Self::EPSILON);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tuner::{dataset, weights::current},
+        uci::bench::FENS,
+    };
+
+    #[test]
+    fn descent_lowers_error() {
+        // Pretend white won every game, so the tuner has something to learn.
+        let entries: Vec<Entry> = FENS
+            .iter()
+            .map(|fen| dataset::entry(&fen.parse().unwrap(), 1.0, None))
+            .collect();
+        let mut weights = current();
+        let k = fit_k(&entries, &weights, 1.0, 2);
+        let mut adam = Adam::new(weights.len(), 1.0);
+
+        let before = error(&entries, &weights, k, 1.0, 2);
+        for _ in 0..20 {
+            let gradient = gradient(&entries, &weights, k, 1.0, 2);
+            adam.step(&mut weights, &gradient);
+        }
+
+        assert!(error(&entries, &weights, k, 1.0, 2) < before);
+    }
+}
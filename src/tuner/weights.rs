@@ -0,0 +1,130 @@
+use crate::search::evaluation::{params, psts::*};
+
+pub const NUM_PARAMS: usize = params::PAWN_THREAT + 1;
+
+// (mg, eg) for every parameter, laid out as in `params`.
+pub type Weights = Vec<[f64; 2]>;
+
+pub fn current() -> Weights {
+    let mut weights = vec![[0.0; 2]; NUM_PARAMS];
+    let mut set = |index: usize, mg: i32, eg: i32| weights[index] = [mg as f64, eg as f64];
+
+    for piece in 0..6 {
+        set(
+            params::PIECE_VALUE + piece,
+            MG_PIECE_VALUES[piece],
+            EG_PIECE_VALUES[piece],
+        );
+        for square in 0..64 {
+            set(
+                params::PST + piece * 64 + square,
+                MG_PST[piece][square],
+                EG_PST[piece][square],
+            );
+        }
+    }
+
+    set(params::DOUBLED_PAWN, MG_DOUBLED_PAWN, EG_DOUBLED_PAWN);
+    set(params::ISOLATED_PAWN, MG_ISOLATED_PAWN, EG_ISOLATED_PAWN);
+    for rank in 0..8 {
+        set(
+            params::PASSED_PAWN + rank,
+            MG_PASSED_PAWN[rank],
+            EG_PASSED_PAWN[rank],
+        );
+    }
+    set(params::BLOCKED_PASSER, MG_BLOCKED_PASSER, EG_BLOCKED_PASSER);
+    set(params::PAWN_THREAT, MG_PAWN_THREAT, EG_PAWN_THREAT);
+
+    weights
+}
+
+fn list(weights: &[[f64; 2]], phase: usize) -> String {
+    let values: Vec<String> = weights
+        .iter()
+        .map(|w| format!("{}", w[phase].round() as i32))
+        .collect();
+    values.join(", ")
+}
+
+fn scalar(out: &mut String, name: &str, weight: [f64; 2]) {
+    for (phase, prefix) in ["MG", "EG"].iter().enumerate() {
+        out.push_str(&format!(
+            "pub const {prefix}_{name}: i32 = {};\n",
+            weight[phase].round() as i32
+        ));
+    }
+}
+
+fn array(out: &mut String, name: &str, weights: &[[f64; 2]]) {
+    for (phase, prefix) in ["MG", "EG"].iter().enumerate() {
+        out.push_str(&format!(
+            "pub const {prefix}_{name}: [i32; {}] = [{}];\n",
+            weights.len(),
+            list(weights, phase)
+        ));
+    }
+}
+
+// Renders the weights as a drop-in replacement for `search/evaluation/psts.rs`.
+pub fn render(weights: &[[f64; 2]]) -> String {
+    const NAMES: [&str; 6] = ["PAWN", "KNIGHT", "BISHOP", "ROOK", "QUEEN", "KING"];
+    const ORIGINAL: &str = include_str!("../search/evaluation/psts.rs");
+
+    let mut out = String::new();
+
+    array(
+        &mut out,
+        "PIECE_VALUES",
+        &weights[params::PIECE_VALUE..][..6],
+    );
+    out.push('\n');
+    scalar(&mut out, "DOUBLED_PAWN", weights[params::DOUBLED_PAWN]);
+    scalar(&mut out, "ISOLATED_PAWN", weights[params::ISOLATED_PAWN]);
+    out.push_str("\n// Indexed by the rank of the pawn, relative to its owner.\n");
+    array(
+        &mut out,
+        "PASSED_PAWN",
+        &weights[params::PASSED_PAWN..][..8],
+    );
+    scalar(&mut out, "BLOCKED_PASSER", weights[params::BLOCKED_PASSER]);
+    out.push_str("\n// Per non-pawn piece attacked by an enemy pawn.\n");
+    scalar(&mut out, "PAWN_THREAT", weights[params::PAWN_THREAT]);
+
+    for (piece, name) in NAMES.iter().enumerate() {
+        for (phase, prefix) in ["MG", "EG"].iter().enumerate() {
+            out.push_str(&format!(
+                "\n#[rustfmt::skip]\nconst {prefix}_{name}_PST: [i32; 64] = [\n"
+            ));
+            for rank in 0..8 {
+                let start = params::PST + piece * 64 + rank * 8;
+                let row: Vec<String> = weights[start..start + 8]
+                    .iter()
+                    .map(|w| format!("{:4}", w[phase].round() as i32))
+                    .collect();
+                out.push_str(&format!("{},\n", row.join(",")));
+            }
+            out.push_str("];\n");
+        }
+    }
+
+    // Everything from here on is code rather than weights.
+    let rest = ORIGINAL.find("pub const MG_PST").unwrap();
+    out.push('\n');
+    out.push_str(&ORIGINAL[rest..]);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let strip = |s: &str| s.split_whitespace().collect::<String>();
+        let original = include_str!("../search/evaluation/psts.rs");
+
+        assert_eq!(strip(&render(&current())), strip(original));
+    }
+}
@@ -0,0 +1,318 @@
+use crate::search::definitions::MATE;
+use cozy_chess::Board;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How long an engine may take to answer `uci` and `isready`.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Score {
+    Cp(i32),
+    // Moves to mate, negative when the engine is getting mated
+    Mate(i32),
+}
+
+impl Score {
+    // Mate scores are mapped the way our search reports them, MATE minus the
+    // plies to mate. Mating in N moves takes 2N - 1 plies, getting mated 2N.
+    pub fn to_cp(self) -> i32 {
+        match self {
+            Score::Cp(cp) => cp,
+            Score::Mate(moves) if moves > 0 => MATE as i32 - (2 * moves - 1),
+            Score::Mate(moves) => -(MATE as i32 + 2 * moves),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    // None for exact scores
+    pub bound: Option<Bound>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<u64>,
+    pub pv: Vec<String>,
+    pub string: Option<String>,
+}
+
+impl Info {
+    // Parses an `info` line, ignoring fields we have no use for.
+    pub fn parse(line: &str) -> Option<Info> {
+        let mut tokens = line.split_ascii_whitespace().peekable();
+        if tokens.next() != Some("info") {
+            return None;
+        }
+
+        let mut info = Info::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = tokens.next().and_then(|x| x.parse().ok()),
+                "seldepth" => info.seldepth = tokens.next().and_then(|x| x.parse().ok()),
+                "multipv" => info.multipv = tokens.next().and_then(|x| x.parse().ok()),
+                "nodes" => info.nodes = tokens.next().and_then(|x| x.parse().ok()),
+                "nps" => info.nps = tokens.next().and_then(|x| x.parse().ok()),
+                "time" => info.time = tokens.next().and_then(|x| x.parse().ok()),
+                "score" => {
+                    let kind = tokens.next();
+                    let value = tokens.next().and_then(|x| x.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(cp)) => Some(Score::Cp(cp)),
+                        (Some("mate"), Some(moves)) => Some(Score::Mate(moves)),
+                        _ => None,
+                    };
+                    info.bound = match tokens.peek() {
+                        Some(&"lowerbound") => Some(Bound::Lower),
+                        Some(&"upperbound") => Some(Bound::Upper),
+                        _ => None,
+                    };
+                    if info.bound.is_some() {
+                        tokens.next();
+                    }
+                }
+                // Both run to the end of the line
+                "pv" => info.pv = tokens.by_ref().map(String::from).collect(),
+                "string" => info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" ")),
+                _ => {}
+            }
+        }
+
+        Some(info)
+    }
+}
+
+pub struct SearchOutput {
+    pub best_move: String,
+    pub info: Vec<Info>,
+}
+
+impl SearchOutput {
+    // The last reported score
+    pub fn score(&self) -> Option<Score> {
+        self.info.iter().rev().find_map(|info| info.score)
+    }
+}
+
+// A UCI engine running as a child process. Its output is read on a separate
+// thread so that waiting for a reply can time out.
+pub struct Engine {
+    pub name: String,
+    pub author: Option<String>,
+    // The names of the options the engine declared during the handshake
+    pub options: Vec<String>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    // Launches the engine, runs the handshake and sets `options`.
+    pub fn start(path: &str, options: &[(String, String)]) -> io::Result<Self> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                match line {
+                    Ok(line) if sender.send(line.trim().to_string()).is_ok() => {}
+                    _ => return,
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            name: path.to_string(),
+            author: None,
+            options: Vec::new(),
+            child,
+            stdin,
+            lines,
+        };
+
+        engine.send("uci")?;
+        let (lines, _) = engine.read_until("uciok", HANDSHAKE_TIMEOUT)?;
+        for line in &lines {
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if let Some(author) = line.strip_prefix("id author ") {
+                engine.author = Some(author.trim().to_string());
+            } else if let Some(option) = line.strip_prefix("option name ") {
+                let name = option.split(" type ").next().unwrap_or(option);
+                engine.options.push(name.trim().to_string());
+            }
+        }
+
+        for (name, value) in options {
+            engine.set_option(name, value)?;
+        }
+        engine.is_ready()?;
+
+        Ok(engine)
+    }
+
+    pub fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    pub fn is_ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        self.read_until("readyok", HANDSHAKE_TIMEOUT).map(|_| ())
+    }
+
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        self.is_ready()
+    }
+
+    // `moves` are in the engine's notation, played from `fen`.
+    pub fn position(&mut self, fen: &str, moves: &[String]) -> io::Result<()> {
+        let mut command = if fen == Board::startpos().to_string() {
+            String::from("position startpos")
+        } else {
+            format!("position fen {fen}")
+        };
+        if !moves.is_empty() {
+            command += " moves ";
+            command += &moves.join(" ");
+        }
+
+        self.send(&command)
+    }
+
+    // Sends `go <limits>` and waits for `bestmove`.
+    pub fn go(&mut self, limits: &str, timeout: Duration) -> io::Result<SearchOutput> {
+        self.send(&format!("go {limits}"))?;
+        let (lines, bestmove) = self.read_until("bestmove", timeout)?;
+
+        let best_move = bestmove.split_ascii_whitespace().nth(1).unwrap_or_default();
+
+        Ok(SearchOutput {
+            best_move: best_move.to_string(),
+            info: lines.iter().filter_map(|line| Info::parse(line)).collect(),
+        })
+    }
+
+    // Returns the lines before the first one starting with `prefix`, and that line.
+    // `Duration::MAX` waits forever.
+    pub fn read_until(
+        &mut self,
+        prefix: &str,
+        timeout: Duration,
+    ) -> io::Result<(Vec<String>, String)> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut lines = Vec::new();
+
+        loop {
+            let line = match deadline {
+                Some(deadline) => self
+                    .lines
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => self
+                    .lines
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match line {
+                Ok(line) if line.starts_with(prefix) => return Ok((lines, line)),
+                Ok(line) => lines.push(line),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("timed out waiting for {prefix}"),
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("engine exited while waiting for {prefix}"),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        // Give the engine a moment to exit on its own before killing it.
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_info() {
+        let info = Info::parse(
+            "info depth 12 seldepth 18 multipv 1 score cp -35 upperbound nodes 12345 nps 600000 time 20 pv e2e4 e7e5 g1f3",
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            Info {
+                depth: Some(12),
+                seldepth: Some(18),
+                multipv: Some(1),
+                score: Some(Score::Cp(-35)),
+                bound: Some(Bound::Upper),
+                nodes: Some(12345),
+                nps: Some(600000),
+                time: Some(20),
+                pv: vec!["e2e4".into(), "e7e5".into(), "g1f3".into()],
+                string: None,
+            }
+        );
+
+        let info = Info::parse("info depth 5 score mate -2 pv e1e2").unwrap();
+        assert_eq!(info.score, Some(Score::Mate(-2)));
+        assert_eq!(info.bound, None);
+        assert_eq!(Score::Mate(-2).to_cp(), -(MATE as i32 - 4));
+        assert_eq!(Score::Mate(3).to_cp(), MATE as i32 - 5);
+        // Sent by some engines when they are mated
+        assert_eq!(Score::Mate(0).to_cp(), -(MATE as i32));
+
+        let info = Info::parse("info string NNUE evaluation enabled").unwrap();
+        assert_eq!(info.string.as_deref(), Some("NNUE evaluation enabled"));
+
+        assert_eq!(Info::parse("bestmove e2e4"), None);
+    }
+}
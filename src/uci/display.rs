@@ -0,0 +1,108 @@
+use crate::chess::{mirror::flip_colors, move_gen::all_moves, parse_move::move_to_string};
+use cozy_chess::{Board, Color, File, Piece, Rank, Square};
+use std::str::SplitAsciiWhitespace;
+
+fn piece_char(piece: Piece, color: Color, unicode: bool) -> char {
+    if unicode {
+        const GLYPHS: [[char; 6]; 2] = [
+            ['♙', '♘', '♗', '♖', '♕', '♔'],
+            ['♟', '♞', '♝', '♜', '♛', '♚'],
+        ];
+        return GLYPHS[color as usize][piece as usize];
+    }
+
+    let c: char = piece.into();
+    match color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+pub fn board_string(board: &Board, unicode: bool) -> String {
+    let mut out = String::new();
+    let separator = " +---+---+---+---+---+---+---+---+\n";
+
+    out.push_str(separator);
+    for rank in Rank::ALL.iter().rev() {
+        for file in File::ALL {
+            let square = Square::new(file, *rank);
+            let c = match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) => piece_char(piece, color, unicode),
+                _ => ' ',
+            };
+            out.push_str(&format!(" | {c}"));
+        }
+        out.push_str(&format!(" | {}\n", *rank as u8 + 1));
+        out.push_str(separator);
+    }
+    out.push_str("   a   b   c   d   e   f   g   h\n");
+
+    out
+}
+
+pub fn d(stream: &mut SplitAsciiWhitespace, board: &Board) {
+    let unicode = stream.next() == Some("unicode");
+    let checkers: Vec<String> = board
+        .checkers()
+        .into_iter()
+        .map(|sq| sq.to_string())
+        .collect();
+    let moves: Vec<String> = all_moves(board)
+        .into_iter()
+        .map(|mv| move_to_string(board, mv))
+        .collect();
+
+    println!();
+    print!("{}", board_string(board, unicode));
+    println!();
+    println!("Fen: {board}");
+    println!("Key: {:016X}", board.hash());
+    println!(
+        "Side to move: {}",
+        match board.side_to_move() {
+            Color::White => "white",
+            Color::Black => "black",
+        }
+    );
+    println!("Checkers: {}", checkers.join(" "));
+    println!("Legal moves ({}): {}", moves.len(), moves.join(" "));
+}
+
+pub fn fen(board: &Board) {
+    println!("{board}");
+}
+
+pub fn flip(board: &mut Board) {
+    *board = flip_colors(board);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn startpos() {
+        let expected = concat!(
+            " +---+---+---+---+---+---+---+---+\n",
+            " | r | n | b | q | k | b | n | r | 8\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " | p | p | p | p | p | p | p | p | 7\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " |   |   |   |   |   |   |   |   | 6\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " |   |   |   |   |   |   |   |   | 5\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " |   |   |   |   |   |   |   |   | 4\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " |   |   |   |   |   |   |   |   | 3\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " | P | P | P | P | P | P | P | P | 2\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            " | R | N | B | Q | K | B | N | R | 1\n",
+            " +---+---+---+---+---+---+---+---+\n",
+            "   a   b   c   d   e   f   g   h\n",
+        );
+
+        assert_eq!(board_string(&Board::startpos(), false), expected);
+    }
+}
@@ -0,0 +1,18 @@
+use crate::search::eval::eval_trace;
+use cozy_chess::Board;
+
+pub fn eval(board: &Board) {
+    println!("{}", eval_trace(board));
+
+    #[cfg(feature = "nnue")]
+    {
+        use crate::search::evaluation::nnue::{accumulator::Accumulator, network};
+
+        let network = network();
+        let score = Accumulator::refresh(&network, board).evaluate(&network, board.side_to_move());
+        println!(
+            "NNUE evaluation: {:.2} (side to move)",
+            score as f64 / 100.0
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use crate::search::{
-    options::SearchOptions,
-    search::{search_root, SearchInfo},
+use crate::{
+    book::BookOptions,
+    chess::{parse_move::move_to_string, perft::PerftOptions},
+    search::{
+        options::SearchOptions,
+        search::{search_root, SearchInfo},
+    },
 };
 use cozy_chess::Board;
 use std::str::SplitAsciiWhitespace;
@@ -53,13 +57,33 @@ fn parse_go(stream: &mut SplitAsciiWhitespace) -> Result<SearchOptions, SearchOp
     }
 }
 
-pub fn go(stream: &mut SplitAsciiWhitespace, info: &mut SearchInfo, board: &Board) {
-    let opts = parse_go(stream);
+pub fn go(
+    stream: &mut SplitAsciiWhitespace,
+    info: &mut SearchInfo,
+    book: &mut BookOptions,
+    perft_options: &PerftOptions,
+    board: &Board,
+) {
+    if stream.clone().next() == Some("perft") {
+        stream.next();
+        super::split::go_perft(stream, board, perft_options);
+        return;
+    }
+
+    let Ok(opts) = parse_go(stream) else {
+        return;
+    };
 
-    if let Ok(opts) = opts {
-        search_root(info, board, opts, false);
-        info.reset();
+    // Infinite searches are for analysis, which wants the engine's own lines.
+    if opts != SearchOptions::Infinite {
+        if let Some(mv) = book.probe(board) {
+            println!("bestmove {}", move_to_string(board, mv));
+            return;
+        }
     }
+
+    search_root(info, board, opts, false);
+    info.reset();
 }
 
 #[cfg(test)]
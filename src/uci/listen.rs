@@ -1,17 +1,25 @@
 use crate::{
+    book::BookOptions,
+    chess::perft::PerftOptions,
     search::search::SearchInfo,
-    uci::{go, options, perft, position, split, ucinewgame},
+    uci::{display, eval, go, options, perft, position, split, ucinewgame},
 };
 use cozy_chess::Board;
 
-pub fn listen() {
+fn uci() {
     println!("id name Honse");
     println!("id author EPD");
     options::print_options();
     println!("uciok");
+}
 
+// The default command loop. The `uci` handshake is optional, and `xboard` hands
+// over to the xboard front end.
+pub fn listen() {
     let mut board = Board::default();
     let mut search_info = SearchInfo::new();
+    let mut book = BookOptions::new();
+    let mut perft_options = PerftOptions::new();
 
     loop {
         let mut input = String::new();
@@ -24,14 +32,35 @@ pub fn listen() {
         let mut stream = input.split_ascii_whitespace();
 
         match stream.next().unwrap_or("") {
-            "ucinewgame" => ucinewgame::ucinewgame(&mut board),
+            "uci" => uci(),
+            "xboard" => {
+                crate::xboard::listen::listen();
+                break;
+            }
+            "ucinewgame" => ucinewgame::ucinewgame(&mut board, &mut search_info),
+            "setoption" => {
+                options::setoption(&mut stream, &mut search_info, &mut book, &mut perft_options)
+            }
             "position" => position::position(&mut stream, &mut board),
-            "perft" => perft::perft(&mut stream, &mut board),
-            "split" => split::split(&mut stream, &mut board),
-            "go" => go::go(&mut stream, &mut search_info, &board),
+            "perft" => perft::perft(&mut stream, &board, &perft_options),
+            "split" => split::split(&mut stream, &board, &perft_options),
+            "d" => display::d(&mut stream, &board),
+            "fen" => display::fen(&board),
+            "flip" => display::flip(&mut board),
+            "eval" => eval::eval(&board),
+            "go" => go::go(
+                &mut stream,
+                &mut search_info,
+                &mut book,
+                &perft_options,
+                &board,
+            ),
             "isready" => println!("readyok"),
             "quit" => break,
-            _ => {}
+            // Searches finish before the next command is read, so there is nothing
+            // to stop, and neither debug output nor registration is supported.
+            "stop" | "ponderhit" | "debug" | "register" | "" => {}
+            command => println!("info string Unknown command: {command}"),
         }
     }
 }
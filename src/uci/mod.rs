@@ -1,4 +1,7 @@
 pub mod bench;
+pub mod client;
+pub mod display;
+pub mod eval;
 pub mod go;
 pub mod listen;
 pub mod options;
@@ -1,4 +1,82 @@
+use crate::{
+    book::{Book, BookOptions, DEFAULT_BOOK_DEPTH},
+    chess::perft::PerftOptions,
+    search::{pawn_table::DEFAULT_PAWN_HASH_MB, search::SearchInfo},
+};
+use std::str::SplitAsciiWhitespace;
+
 pub fn print_options() {
-    println!("option name Hash type spin default 0 min 0 max 0");
-    println!("option name Threads type spin default 1 min 1 max 1");
+    // Only perft uses these so far, the search runs on one thread without a
+    // transposition table.
+    println!("info string Hash and Threads apply to perft and split only");
+    println!("option name Hash type spin default 0 min 0 max 4096");
+    println!("option name Threads type spin default 1 min 1 max 256");
+    println!("option name PawnHash type spin default {DEFAULT_PAWN_HASH_MB} min 1 max 256");
+    println!("option name OwnBook type check default false");
+    println!("option name BookFile type string default <empty>");
+    println!("option name BookDepth type spin default {DEFAULT_BOOK_DEPTH} min 1 max 1000");
+    println!("option name BookBestMove type check default false");
+    #[cfg(feature = "nnue")]
+    println!("option name EvalFile type string default <default>");
+}
+
+pub fn setoption(
+    stream: &mut SplitAsciiWhitespace,
+    info: &mut SearchInfo,
+    book: &mut BookOptions,
+    perft: &mut PerftOptions,
+) {
+    if stream.next() != Some("name") {
+        return;
+    }
+
+    let name: Vec<&str> = stream
+        .by_ref()
+        .take_while(|&part| part != "value")
+        .collect();
+    let value: Vec<&str> = stream.collect();
+
+    let (name, value) = (name.join(" "), value.join(" "));
+
+    match name.as_str() {
+        "Hash" => {
+            if let Ok(mb) = value.parse::<usize>() {
+                perft.set_hash(mb.min(4096));
+            }
+        }
+        "Threads" => {
+            if let Ok(threads) = value.parse::<usize>() {
+                perft.threads = threads.clamp(1, 256);
+            }
+        }
+        "PawnHash" => {
+            if let Ok(mb) = value.parse::<usize>() {
+                info.evaluator.pawn_table.resize(mb.clamp(1, 256));
+            }
+        }
+        "OwnBook" => book.own_book = value == "true",
+        "BookFile" if value.is_empty() || value == "<empty>" => book.book = None,
+        "BookFile" => match Book::load(&value) {
+            Ok(loaded) => {
+                println!(
+                    "info string loaded book {value} ({} entries)",
+                    loaded.num_entries()
+                );
+                book.book = Some(loaded);
+            }
+            Err(e) => println!("info string failed to load book {value}: {e}"),
+        },
+        "BookDepth" => {
+            if let Ok(depth) = value.parse::<u16>() {
+                book.depth = depth.clamp(1, 1000);
+            }
+        }
+        "BookBestMove" => book.best_move = value == "true",
+        #[cfg(feature = "nnue")]
+        "EvalFile" => match crate::search::evaluation::nnue::load_network(&value) {
+            Ok(()) => println!("info string loaded network {value}"),
+            Err(e) => println!("info string failed to load network {value}: {e}"),
+        },
+        _ => {}
+    }
 }
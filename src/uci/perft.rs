@@ -1,16 +1,90 @@
+use super::position::parse_position;
+use crate::chess::perft::{perft_detailed, PerftOptions};
+use cozy_chess::Board;
 use std::str::SplitAsciiWhitespace;
 use std::time::Instant;
 
-pub fn perft(stream: &mut SplitAsciiWhitespace, board: &mut cozy_chess::Board) {
-    let depth = match stream.next().map(|x| x.parse::<u8>()) {
-        Some(Ok(depth)) => depth,
-        Some(Err(_)) => return,
-        None => return,
+pub struct PerftCommand {
+    pub board: Board,
+    pub depth: u8,
+    pub options: PerftOptions,
+    pub detailed: bool,
+}
+
+// `<depth> [detailed] [threads <n>] [hash <mb>] [startpos | fen <fen>] [moves ...]`.
+// The overrides apply to this command only, and a position given inline is used
+// instead of the current board, which is left untouched.
+pub fn parse_perft(
+    stream: &mut SplitAsciiWhitespace,
+    board: &Board,
+    options: &PerftOptions,
+) -> Option<PerftCommand> {
+    let depth = stream.next()?.parse::<u8>().ok()?;
+    let tokens: Vec<&str> = stream.collect();
+    let start = tokens
+        .iter()
+        .position(|&x| matches!(x, "startpos" | "fen" | "moves"))
+        .unwrap_or(tokens.len());
+
+    let mut command = PerftCommand {
+        board: board.clone(),
+        depth,
+        options: options.clone(),
+        detailed: false,
+    };
+
+    let mut overrides = tokens[..start].iter();
+    while let Some(&name) = overrides.next() {
+        if name == "detailed" {
+            command.detailed = true;
+            continue;
+        }
+
+        let value = overrides
+            .next()
+            .and_then(|value| value.parse::<usize>().ok());
+        match (name, value) {
+            ("threads", Some(threads)) => command.options.threads = threads.clamp(1, 256),
+            ("hash", Some(mb)) => command.options.set_hash(mb.min(4096)),
+            _ => {}
+        }
+    }
+
+    if start < tokens.len() {
+        let inline = tokens[start..].join(" ");
+        match parse_position(&mut inline.split_ascii_whitespace(), board) {
+            Ok(board) => command.board = board,
+            Err(e) => {
+                println!("info string {e}");
+                return None;
+            }
+        }
+    }
+
+    Some(command)
+}
+
+pub fn perft(stream: &mut SplitAsciiWhitespace, board: &Board, options: &PerftOptions) {
+    let Some(command) = parse_perft(stream, board, options) else {
+        return;
     };
+    let board = &command.board;
 
-    for i in 1..=depth {
+    for i in 1..=command.depth {
         let start = Instant::now();
-        let nodes = crate::chess::perft::perft(board, i);
+
+        if command.detailed {
+            let stats = perft_detailed(board, i);
+            let duration = start.elapsed();
+            println!("info depth {} {} time {:?}", i, stats, duration.as_millis());
+
+            if i == command.depth {
+                println!("nodes {}", stats.nodes);
+            }
+            continue;
+        }
+
+        let nodes = command.options.perft(board, i);
         let duration = start.elapsed();
         let nps = nodes as f64 / duration.as_secs_f64();
         println!(
@@ -21,8 +95,44 @@ pub fn perft(stream: &mut SplitAsciiWhitespace, board: &mut cozy_chess::Board) {
             nps as u64
         );
 
-        if i == depth {
+        if i == command.depth {
             println!("nodes {nodes}");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_position() {
+        let board = Board::startpos();
+        let options = PerftOptions::new();
+
+        let input = "3 detailed threads 4 fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1 moves e1h1";
+        let command = parse_perft(&mut input.split_ascii_whitespace(), &board, &options).unwrap();
+        assert_eq!(command.depth, 3);
+        assert!(command.detailed);
+        assert_eq!(command.options.threads, 4);
+        assert_eq!(
+            command.board,
+            "4k3/8/8/8/8/8/8/5RK1 b - - 1 1".parse().unwrap()
+        );
+
+        // Moves alone are played from the current board.
+        let input = "2 moves e2e4";
+        let command = parse_perft(&mut input.split_ascii_whitespace(), &board, &options).unwrap();
+        assert_eq!(command.options.threads, 1);
+        assert_eq!(
+            command.board,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+                .parse()
+                .unwrap()
+        );
+
+        assert!(parse_perft(&mut "x".split_ascii_whitespace(), &board, &options).is_none());
+        let input = "2 moves e2e5";
+        assert!(parse_perft(&mut input.split_ascii_whitespace(), &board, &options).is_none());
+    }
+}
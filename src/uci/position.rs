@@ -1,27 +1,46 @@
+use crate::chess::parse_move::parse_move;
+use cozy_chess::Board;
 use std::str::SplitAsciiWhitespace;
 
-pub fn position(stream: &mut SplitAsciiWhitespace, board: &mut cozy_chess::Board) {
-    // Parse startpos/fen
-    match stream.next() {
+// Parses `startpos | fen <fen>` and an optional `moves ...` list, or a move list
+// alone played from `board`. Both standard and Shredder FENs are accepted, and
+// every move must be legal.
+pub fn parse_position(stream: &mut SplitAsciiWhitespace, board: &Board) -> Result<Board, String> {
+    let mut board = match stream.next() {
         Some("startpos") => {
-            *board = cozy_chess::Board::startpos();
-            stream.next();
+            match stream.next() {
+                None | Some("moves") => {}
+                Some(x) => return Err(format!("Expected moves, found {x}")),
+            }
+            Board::startpos()
         }
         Some("fen") => {
-            let fen: String = stream
-                .take_while(|&part| part != "moves")
-                .fold(String::new(), |a, b| a + b + " ");
-            *board = fen.trim().parse().unwrap();
+            let fen: Vec<&str> = stream.take_while(|&part| part != "moves").collect();
+            let fen = fen.join(" ");
+            fen.parse::<Board>()
+                .map_err(|e| format!("Invalid FEN {fen}: {e}"))?
         }
-        _ => {}
-    }
+        Some("moves") => board.clone(),
+        Some(x) => return Err(format!("Expected startpos, fen or moves, found {x}")),
+        None => return Err(String::from("Missing position")),
+    };
 
-    // Parse moves
     for x in stream.by_ref() {
-        match crate::chess::parse_move::parse_move(board, x) {
-            Ok(mv) => board.play(mv),
-            _ => break,
+        let mv = parse_move(&board, x).map_err(|_| format!("Invalid move {x}"))?;
+        if !board.is_legal(mv) {
+            return Err(format!("Illegal move {x} in {board}"));
         }
+        board.play_unchecked(mv);
+    }
+
+    Ok(board)
+}
+
+// On error the previous position is kept.
+pub fn position(stream: &mut SplitAsciiWhitespace, board: &mut Board) {
+    match parse_position(stream, board) {
+        Ok(position) => *board = position,
+        Err(e) => println!("info string {e}"),
     }
 }
 
@@ -122,4 +141,79 @@ mod tests {
             assert_eq!(board, expected);
         }
     }
+
+    #[test]
+    fn errors() {
+        let tests: [(&str, &str); 12] = [
+            ("", "Missing position"),
+            ("startfen", "Expected startpos, fen or moves"),
+            ("startpos e2e4", "Expected moves"),
+            // Wrong field count
+            (
+                "fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                "missing a field",
+            ),
+            (
+                "fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 1",
+                "too many fields",
+            ),
+            (
+                "fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+                "board is invalid",
+            ),
+            // Impossible positions: no white king, and the side not to move in check
+            ("fen 4k3/8/8/8/8/8/8/8 w - - 0 1", "board is invalid"),
+            ("fen 4k3/4R3/8/8/8/8/8/4K3 w - - 0 1", "board is invalid"),
+            (
+                "fen 4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1",
+                "castling rights are invalid",
+            ),
+            (
+                "fen 4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+                "en passant square is invalid",
+            ),
+            ("startpos moves e2e4 e7e5 e1g1", "Illegal move e1g1"),
+            (
+                "fen 4k3/8/8/8/8/8/8/4K2R w - - 0 1 moves h1h8 h8h7",
+                "Illegal move h8h7",
+            ),
+        ];
+
+        for (input, error) in tests {
+            let e = parse_position(&mut input.split_ascii_whitespace(), &Board::startpos())
+                .unwrap_err();
+            assert!(e.contains(error), "{input}: {e}");
+        }
+
+        let board = Board::startpos();
+        let e = parse_position(&mut "moves e2e4 x".split_ascii_whitespace(), &board).unwrap_err();
+        assert_eq!(e, "Invalid move x");
+
+        // The previous position is kept.
+        let mut board = board;
+        position(
+            &mut "startpos moves d2d4".split_ascii_whitespace(),
+            &mut board,
+        );
+        let expected = board.clone();
+        position(
+            &mut "startpos moves d2d4 d2d4".split_ascii_whitespace(),
+            &mut board,
+        );
+        assert_eq!(board, expected);
+        position(
+            &mut "fen 8/8/8/8/8/8/8/8 w - - 0 1".split_ascii_whitespace(),
+            &mut board,
+        );
+        assert_eq!(board, expected);
+
+        // Shredder FENs are accepted.
+        let board = parse_position(
+            &mut "fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+                .split_ascii_whitespace(),
+            &Board::startpos(),
+        )
+        .unwrap();
+        assert_eq!(board, Board::startpos());
+    }
 }
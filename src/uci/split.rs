@@ -1,23 +1,33 @@
+use super::perft::parse_perft;
+use crate::chess::{parse_move::move_to_string, perft::PerftOptions};
+use cozy_chess::Board;
 use std::str::SplitAsciiWhitespace;
 
-pub fn split(stream: &mut SplitAsciiWhitespace, board: &mut cozy_chess::Board) {
-    let depth = match stream.next().map(|x| x.parse::<u8>()) {
-        Some(Ok(0)) => return,
-        Some(Ok(depth)) => depth,
-        Some(Err(_)) => return,
-        None => return,
+pub fn split(stream: &mut SplitAsciiWhitespace, board: &Board, options: &PerftOptions) {
+    let Some(command) = parse_perft(stream, board, options).filter(|x| x.depth > 0) else {
+        return;
     };
 
     let mut total = 0;
-    board.generate_moves(|moves| {
-        for mv in moves {
-            let mut board = board.clone();
-            board.play_unchecked(mv);
-            let nodes = crate::chess::perft::perft(&board, depth - 1);
-            total += nodes;
-            println!("{mv} {nodes}");
-        }
-        false
-    });
+    for (mv, nodes) in command.options.divide(&command.board, command.depth) {
+        total += nodes;
+        println!("{} {nodes}", move_to_string(&command.board, mv));
+    }
     println!("nodes {total}");
 }
+
+// `go perft <depth>`, in the format Stockfish uses so that perft debugging tools
+// written against it work unchanged.
+pub fn go_perft(stream: &mut SplitAsciiWhitespace, board: &Board, options: &PerftOptions) {
+    let Some(command) = parse_perft(stream, board, options).filter(|x| x.depth > 0) else {
+        return;
+    };
+
+    let mut total = 0;
+    for (mv, nodes) in command.options.divide(&command.board, command.depth) {
+        total += nodes;
+        println!("{}: {nodes}", move_to_string(&command.board, mv));
+    }
+    println!();
+    println!("Nodes searched: {total}");
+}
@@ -1,5 +1,8 @@
-pub fn ucinewgame(board: &mut cozy_chess::Board) {
+use crate::search::search::SearchInfo;
+
+pub fn ucinewgame(board: &mut cozy_chess::Board, info: &mut SearchInfo) {
     *board = cozy_chess::Board::startpos();
+    info.evaluator.pawn_table.clear();
 }
 
 #[cfg(test)]
@@ -13,7 +16,7 @@ mod tests {
             .unwrap();
         let expected = cozy_chess::Board::startpos();
         assert_ne!(board, expected);
-        ucinewgame(&mut board);
+        ucinewgame(&mut board, &mut SearchInfo::new());
         assert_eq!(board, expected);
     }
 }
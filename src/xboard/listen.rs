@@ -0,0 +1,344 @@
+use crate::{
+    chess::parse_move::{move_to_string, parse_move},
+    search::{
+        options::SearchOptions,
+        search::{search_root, Output, SearchInfo},
+    },
+    uci::ucinewgame::ucinewgame,
+};
+use cozy_chess::{Board, Color, GameStatus, Move};
+
+const FEATURES: &str = "feature myname=\"Honse\" ping=1 setboard=1 usermove=1 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 san=0 done=1";
+
+struct Xboard {
+    board: Board,
+    // The positions before each move, for undo
+    history: Vec<Board>,
+    search_info: SearchInfo,
+    // In force mode the engine plays neither side.
+    force: bool,
+    engine: Color,
+    post: bool,
+    // `level`: moves per session (0 for the whole game), base and increment in ms
+    moves_per_session: u32,
+    base: u32,
+    increment: u32,
+    // `st`, in ms
+    move_time: Option<u32>,
+    // `sd`
+    depth: Option<u8>,
+    // `time` and `otim`, in ms
+    time: u32,
+    opponent_time: u32,
+}
+
+// `level` base times are minutes, optionally with seconds: `5` or `0:30`.
+fn parse_base(base: &str) -> Option<u32> {
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    Some((minutes.parse::<u32>().ok()? * 60 + seconds.parse::<u32>().ok()?) * 1000)
+}
+
+impl Xboard {
+    fn new() -> Self {
+        let mut xboard = Xboard {
+            board: Board::startpos(),
+            history: Vec::new(),
+            search_info: SearchInfo::new(),
+            force: false,
+            engine: Color::Black,
+            post: false,
+            moves_per_session: 40,
+            base: 5 * 60 * 1000,
+            increment: 0,
+            move_time: None,
+            depth: None,
+            time: 0,
+            opponent_time: 0,
+        };
+        xboard.time = xboard.base;
+        xboard.opponent_time = xboard.base;
+        xboard
+    }
+
+    fn limits(&self) -> SearchOptions {
+        if let Some(move_time) = self.move_time {
+            return SearchOptions::Movetime(move_time);
+        }
+        if let Some(depth) = self.depth {
+            return SearchOptions::Depth(depth);
+        }
+
+        let (wtime, btime) = match self.engine {
+            Color::White => (self.time, self.opponent_time),
+            Color::Black => (self.opponent_time, self.time),
+        };
+        let moves_to_go = (self.moves_per_session > 0).then(|| {
+            let played = self.board.fullmove_number() as u32 - 1;
+            self.moves_per_session - played % self.moves_per_session
+        });
+
+        SearchOptions::Time(
+            wtime,
+            btime,
+            Some(self.increment),
+            Some(self.increment),
+            moves_to_go,
+        )
+    }
+
+    fn play(&mut self, mv: Move) {
+        self.history.push(self.board.clone());
+        self.board.play_unchecked(mv);
+    }
+
+    // The result and reason to announce if the game is over.
+    fn game_over(&self) -> Option<&'static str> {
+        let repetitions = self
+            .history
+            .iter()
+            .filter(|board| board.hash() == self.board.hash())
+            .count();
+
+        match self.board.status() {
+            GameStatus::Won => Some(match self.board.side_to_move() {
+                Color::White => "0-1 {Black mates}",
+                Color::Black => "1-0 {White mates}",
+            }),
+            GameStatus::Drawn if self.board.halfmove_clock() >= 100 => {
+                Some("1/2-1/2 {Fifty move rule}")
+            }
+            GameStatus::Drawn => Some("1/2-1/2 {Stalemate}"),
+            GameStatus::Ongoing if repetitions >= 2 => Some("1/2-1/2 {Draw by repetition}"),
+            GameStatus::Ongoing => None,
+        }
+    }
+
+    fn think(&mut self) {
+        if let Some(result) = self.game_over() {
+            println!("{result}");
+            return;
+        }
+
+        self.search_info.output = if self.post {
+            Output::Xboard
+        } else {
+            Output::Silent
+        };
+        let limits = self.limits();
+        let result = search_root(&mut self.search_info, &self.board, limits, false);
+        self.search_info.reset();
+        let Some(result) = result else {
+            return;
+        };
+
+        println!("move {}", move_to_string(&self.board, result.best_move));
+        self.play(result.best_move);
+
+        if let Some(result) = self.game_over() {
+            println!("{result}");
+        }
+    }
+
+    fn usermove(&mut self, movestr: &str) {
+        let mv = parse_move(&self.board, movestr)
+            .ok()
+            .filter(|&mv| self.board.is_legal(mv));
+        let Some(mv) = mv else {
+            println!("Illegal move: {movestr}");
+            return;
+        };
+
+        self.play(mv);
+        if !self.force && self.board.side_to_move() == self.engine {
+            self.think();
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(board) = self.history.pop() {
+            self.board = board;
+        }
+    }
+
+    // Returns false on `quit`.
+    fn handle(&mut self, line: &str) -> bool {
+        let mut stream = line.split_ascii_whitespace();
+        let command = stream.next().unwrap_or("");
+        let number = |x: Option<&str>| x.and_then(|x| x.parse::<f64>().ok());
+
+        match command {
+            "protover" => println!("{FEATURES}"),
+            "new" => {
+                ucinewgame(&mut self.board, &mut self.search_info);
+                self.history.clear();
+                self.force = false;
+                self.engine = Color::Black;
+                self.depth = None;
+                self.time = self.base;
+                self.opponent_time = self.base;
+            }
+            "setboard" => {
+                let fen = stream.collect::<Vec<_>>().join(" ");
+                match fen.parse::<Board>() {
+                    Ok(board) => {
+                        self.board = board;
+                        self.history.clear();
+                    }
+                    Err(_) => println!("tellusererror Illegal position"),
+                }
+            }
+            "usermove" => match stream.next() {
+                Some(mv) => self.usermove(mv),
+                None => println!("Error (missing move): usermove"),
+            },
+            "go" => {
+                self.force = false;
+                self.engine = self.board.side_to_move();
+                self.think();
+            }
+            "force" | "result" => self.force = true,
+            "level" => {
+                let mps = stream.next().and_then(|x| x.parse().ok());
+                let base = stream.next().and_then(parse_base);
+                let increment = number(stream.next());
+                match (mps, base, increment) {
+                    (Some(mps), Some(base), Some(increment)) => {
+                        self.moves_per_session = mps;
+                        self.base = base;
+                        self.increment = (increment * 1000.0) as u32;
+                        self.move_time = None;
+                        self.time = base;
+                        self.opponent_time = base;
+                    }
+                    _ => println!("Error (bad arguments): {}", line.trim()),
+                }
+            }
+            "st" => match number(stream.next()) {
+                Some(seconds) => self.move_time = Some((seconds * 1000.0) as u32),
+                None => println!("Error (bad arguments): {}", line.trim()),
+            },
+            "sd" => match stream.next().and_then(|x| x.parse().ok()) {
+                Some(depth) => self.depth = Some(depth),
+                None => println!("Error (bad arguments): {}", line.trim()),
+            },
+            // Both in centiseconds
+            "time" => self.time = number(stream.next()).map_or(self.time, |x| x as u32 * 10),
+            "otim" => {
+                self.opponent_time =
+                    number(stream.next()).map_or(self.opponent_time, |x| x as u32 * 10)
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "undo" => self.undo(),
+            "remove" => {
+                self.undo();
+                self.undo();
+            }
+            "ping" => println!("pong {}", stream.next().unwrap_or("")),
+            "quit" => return false,
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "?" | "draw" | "white" | "black" => {}
+            // Without `usermove=1`, moves are sent on their own.
+            _ if parse_move(&self.board, command).is_ok() => self.usermove(command),
+            _ => println!("Error (unknown command): {command}"),
+        }
+
+        true
+    }
+}
+
+pub fn listen() {
+    let mut xboard = Xboard::new();
+
+    loop {
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+
+        if !xboard.handle(&input) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xboard(commands: &[&str]) -> Xboard {
+        let mut xboard = Xboard::new();
+        for command in commands {
+            assert!(xboard.handle(command));
+        }
+        xboard
+    }
+
+    #[test]
+    fn moves_and_undo() {
+        let x = xboard(&["new", "force", "usermove e2e4", "e7e5", "usermove e1e3"]);
+        assert_eq!(x.history.len(), 2);
+        assert_eq!(
+            x.board,
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+                .parse()
+                .unwrap()
+        );
+
+        let x = xboard(&["new", "force", "e2e4", "e7e5", "remove"]);
+        assert_eq!(x.board, Board::startpos());
+        let x = xboard(&["new", "force", "e2e4", "undo", "undo"]);
+        assert_eq!(x.board, Board::startpos());
+
+        let x = xboard(&[
+            "setboard 4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            "force",
+            "usermove e1g1",
+        ]);
+        assert_eq!(x.board, "4k3/8/8/8/8/8/8/5RK1 b - - 1 1".parse().unwrap());
+    }
+
+    #[test]
+    fn time_controls() {
+        let x = xboard(&["new", "level 40 0:30 0", "time 2500", "otim 3000"]);
+        assert_eq!(x.base, 30_000);
+        assert_eq!(
+            x.limits(),
+            SearchOptions::Time(30_000, 25_000, Some(0), Some(0), Some(40))
+        );
+
+        let x = xboard(&["new", "level 0 5 1.5", "force", "e2e4", "time 1000"]);
+        assert_eq!(
+            x.limits(),
+            SearchOptions::Time(300_000, 10_000, Some(1500), Some(1500), None)
+        );
+
+        let x = xboard(&["new", "level 40 5 0", "sd 4"]);
+        assert_eq!(x.limits(), SearchOptions::Depth(4));
+        let x = xboard(&["new", "st 2", "sd 4"]);
+        assert_eq!(x.limits(), SearchOptions::Movetime(2000));
+        // `new` removes the depth limit
+        let x = xboard(&["sd 4", "new"]);
+        assert!(matches!(x.limits(), SearchOptions::Time(..)));
+    }
+
+    #[test]
+    fn engine_moves() {
+        // The engine plays black and answers immediately.
+        let x = xboard(&["new", "sd 2", "usermove e2e4"]);
+        assert_eq!(x.history.len(), 2);
+        assert_eq!(x.board.side_to_move(), Color::White);
+
+        // `go` makes the engine play the side to move.
+        let x = xboard(&["new", "sd 2", "force", "e2e4", "e7e5", "go"]);
+        assert_eq!(x.engine, Color::White);
+        assert_eq!(x.history.len(), 3);
+
+        // No move is made once the game is over.
+        let x = xboard(&["setboard 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", "sd 2", "go"]);
+        assert!(x.history.is_empty());
+        assert_eq!(x.game_over(), Some("1-0 {White mates}"));
+    }
+}
@@ -0,0 +1,20 @@
+// Runs the bundled perft suite through the binary. Slow, so it is behind a feature:
+// `cargo test --release --features perftsuite`
+#![cfg(feature = "perftsuite")]
+
+use std::process::Command;
+
+#[test]
+fn perft_suite() {
+    let suite = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/perftsuite.epd");
+    let output = Command::new(env!("CARGO_BIN_EXE_honse"))
+        .args(["perftsuite", suite])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
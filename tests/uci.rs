@@ -0,0 +1,45 @@
+// Drives the binary cargo just built over UCI. The client's parsing is covered
+// by the unit tests in src/uci/client.rs.
+use cozy_chess::{Board, Move};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn drive_honse() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_honse"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Honse does not read input while searching, so quit is only read afterwards.
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"uci\nisready\nucinewgame\nposition startpos moves e2e4\ngo depth 3\nquit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.contains(&"id name Honse"));
+    assert!(lines.contains(&"uciok"));
+    assert!(lines.contains(&"readyok"));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("info depth 3 ") && line.contains(" score ")));
+
+    let best_move = lines
+        .iter()
+        .find_map(|line| line.strip_prefix("bestmove "))
+        .unwrap();
+    let board: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        .parse()
+        .unwrap();
+    let mv: Move = best_move.parse().unwrap();
+    assert!(board.is_legal(mv));
+}